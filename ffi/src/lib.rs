@@ -0,0 +1,152 @@
+//! A C ABI for rendering text with the `text` crate, built as a shared library.
+
+use {
+    std::{
+        ffi::{
+            CStr,
+            c_char,
+            c_int,
+        },
+        ptr,
+        slice,
+    },
+    fontdue::{
+        Font,
+        layout::{
+            CoordinateSystem,
+            HorizontalAlign,
+            Layout,
+        },
+    },
+    tiny_skia::{
+        Color,
+        ColorU8,
+        PixmapMut,
+    },
+    text::{
+        Builder,
+        DEFAULT_SIZE,
+        GlyphCache,
+        VerticalAlign,
+    },
+};
+
+pub struct TextRenderer {
+    font: Font,
+    text: String,
+    color: ColorU8,
+    size: f32,
+    halign: HorizontalAlign,
+    valign: VerticalAlign,
+    layout: Layout,
+    glyph_cache: GlyphCache,
+}
+
+/// Returns null if the font could not be parsed.
+///
+/// # Safety
+///
+/// `font_data` must point to `font_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn text_renderer_new(font_data: *const u8, font_len: usize) -> *mut TextRenderer {
+    if font_data.is_null() { return ptr::null_mut() }
+    let Ok(font) = text::font_from_bytes(unsafe { slice::from_raw_parts(font_data, font_len) }) else { return ptr::null_mut() };
+    Box::into_raw(Box::new(TextRenderer {
+        text: String::default(),
+        color: Color::WHITE.to_color_u8(),
+        size: DEFAULT_SIZE,
+        halign: HorizontalAlign::Center,
+        valign: VerticalAlign::Middle,
+        layout: Layout::new(CoordinateSystem::PositiveYDown),
+        glyph_cache: GlyphCache::default(),
+        font,
+    }))
+}
+
+/// # Safety
+///
+/// `renderer` must be null or have been returned by `text_renderer_new` and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn text_renderer_free(renderer: *mut TextRenderer) {
+    if !renderer.is_null() {
+        drop(unsafe { Box::from_raw(renderer) });
+    }
+}
+
+/// Returns 0 on success or -1 if the text is not valid UTF-8.
+///
+/// # Safety
+///
+/// `renderer` must be a live renderer and `text` a nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn text_renderer_set_text(renderer: *mut TextRenderer, text: *const c_char) -> c_int {
+    let (Some(renderer), false) = (unsafe { renderer.as_mut() }, text.is_null()) else { return -1 };
+    let Ok(text) = unsafe { CStr::from_ptr(text) }.to_str() else { return -1 };
+    renderer.text = text.to_owned();
+    0
+}
+
+/// # Safety
+///
+/// `renderer` must be a live renderer.
+#[no_mangle]
+pub unsafe extern "C" fn text_renderer_set_color(renderer: *mut TextRenderer, red: u8, green: u8, blue: u8, alpha: u8) {
+    if let Some(renderer) = unsafe { renderer.as_mut() } {
+        renderer.color = ColorU8::from_rgba(red, green, blue, alpha);
+    }
+}
+
+/// # Safety
+///
+/// `renderer` must be a live renderer.
+#[no_mangle]
+pub unsafe extern "C" fn text_renderer_set_size(renderer: *mut TextRenderer, size: f32) {
+    if let Some(renderer) = unsafe { renderer.as_mut() } {
+        renderer.size = size;
+    }
+}
+
+/// `halign` is 0 for left, 1 for center, 2 for right. `valign` is 0 for top, 1 for middle, 2 for bottom.
+///
+/// # Safety
+///
+/// `renderer` must be a live renderer.
+#[no_mangle]
+pub unsafe extern "C" fn text_renderer_set_align(renderer: *mut TextRenderer, halign: c_int, valign: c_int) -> c_int {
+    let Some(renderer) = (unsafe { renderer.as_mut() }) else { return -1 };
+    renderer.halign = match halign {
+        0 => HorizontalAlign::Left,
+        1 => HorizontalAlign::Center,
+        2 => HorizontalAlign::Right,
+        _ => return -1,
+    };
+    renderer.valign = match valign {
+        0 => VerticalAlign::Top,
+        1 => VerticalAlign::Middle,
+        2 => VerticalAlign::Bottom,
+        _ => return -1,
+    };
+    0
+}
+
+/// Draws the text onto a premultiplied RGBA8 buffer of `width * height * 4` bytes. Returns 0 on success or -1 on error.
+///
+/// # Safety
+///
+/// `renderer` must be a live renderer and `buffer` must point to `width * height * 4` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn text_renderer_draw(renderer: *mut TextRenderer, buffer: *mut u8, width: u32, height: u32) -> c_int {
+    let (Some(renderer), false) = (unsafe { renderer.as_mut() }, buffer.is_null()) else { return -1 };
+    let buffer = unsafe { slice::from_raw_parts_mut(buffer, width as usize * height as usize * 4) };
+    let Some(canvas) = PixmapMut::from_bytes(buffer, width, height) else { return -1 };
+    let Ok(text_box) = Builder::new(&renderer.font, &renderer.text)
+        .color(renderer.color)
+        .size(renderer.size)
+        .halign(renderer.halign)
+        .valign(renderer.valign)
+        .build(&mut renderer.layout, [width as f32, height as f32]) else { return -1 };
+    match text_box.draw(canvas, &mut renderer.glyph_cache) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
@@ -0,0 +1,52 @@
+use {
+    alloc::vec::Vec,
+    tiny_skia::Rect,
+    crate::{
+        TextBox,
+        TextSpan,
+    },
+};
+
+/// A description of laid out text for accessibility trees or tagged PDF text. All lists are in reading order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessibleText<'a> {
+    /// The text appended to the text box, after preprocessing such as newline normalization. All byte ranges refer to this.
+    pub text: &'a str,
+    pub bounds: Rect,
+    pub lines: Vec<AccessibleLine<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessibleLine<'a> {
+    pub line: TextSpan<'a>,
+    /// The runs of non-whitespace text on this line.
+    pub words: Vec<TextSpan<'a>>,
+    /// Maps each glyph on this line to the text it was laid out from.
+    pub glyphs: Vec<TextSpan<'a>>,
+}
+
+impl TextBox<'_, '_> {
+    pub fn accessible_text(&self) -> AccessibleText<'_> {
+        let offsets = self.glyph_byte_offsets();
+        let mut words = self.words().peekable();
+        let lines = self.lines().map(|line| AccessibleLine {
+            words: core::iter::from_fn(|| words.next_if(|word| word.range.start < line.range.end)).collect(),
+            glyphs: self.glyphs.iter()
+                .zip(&offsets)
+                .filter(|&(glyph, &offset)| glyph.parent != '\n' && line.range.contains(&offset))
+                .filter_map(|(glyph, &offset)| {
+                    let range = offset..offset + glyph.parent.len_utf8();
+                    let (left, right) = self.glyph_extent(glyph);
+                    Some(TextSpan {
+                        text: self.text.get(range.clone()).unwrap_or_default(),
+                        rect: Rect::from_ltrb(left, line.rect.top(), right, line.rect.bottom())?,
+                        range,
+                    })
+                })
+                .collect(),
+            line,
+        }).collect::<Vec<_>>();
+        let bounds = self.rect_inner().unwrap_or(self.inner_bounds);
+        AccessibleText { text: &self.text, bounds, lines }
+    }
+}
@@ -0,0 +1,103 @@
+use {
+    fontdue::{
+        Font,
+        layout::{
+            CoordinateSystem,
+            HorizontalAlign,
+            Layout,
+        },
+    },
+    tiny_skia::{
+        Color,
+        ColorU8,
+        FillRule,
+        Paint,
+        Pixmap,
+        Rect,
+        Transform,
+    },
+    crate::{
+        Builder,
+        Error,
+        GlyphCache,
+        IntoColor,
+        VerticalAlign,
+        rounded_rect,
+    },
+};
+#[cfg(not(any(feature = "std", test)))] use crate::float::Float as _;
+
+/// A two-segment label/value badge in the style of shields.io.
+#[derive(Debug, Clone)]
+pub struct Badge<'a> {
+    pub label: &'a str,
+    pub value: &'a str,
+    pub label_background: ColorU8,
+    pub value_background: ColorU8,
+    pub text_color: ColorU8,
+    pub size: f32,
+    pub height: f32,
+    /// Horizontal space between the text and the edge of its segment.
+    pub padding: f32,
+    pub corner_radius: f32,
+}
+
+impl<'a> Badge<'a> {
+    pub fn new(label: &'a str, value: &'a str) -> Self {
+        Self {
+            label_background: ColorU8::from_rgba(0x55, 0x55, 0x55, u8::MAX),
+            value_background: ColorU8::from_rgba(0x44, 0xcc, 0x11, u8::MAX),
+            text_color: ColorU8::from_rgba(u8::MAX, u8::MAX, u8::MAX, u8::MAX),
+            size: 11.0,
+            height: 20.0,
+            padding: 6.0,
+            corner_radius: 3.0,
+            label, value,
+        }
+    }
+
+    pub fn value_background(self, color: impl IntoColor) -> Self {
+        Self { value_background: color.into_color_u8(), ..self }
+    }
+
+    pub fn label_background(self, color: impl IntoColor) -> Self {
+        Self { label_background: color.into_color_u8(), ..self }
+    }
+
+    pub fn text_color(self, color: impl IntoColor) -> Self {
+        Self { text_color: color.into_color_u8(), ..self }
+    }
+
+    pub fn render(&self, font: &Font, glyph_cache: &mut GlyphCache) -> Result<Pixmap, Error> {
+        let mut layout = Layout::new(CoordinateSystem::PositiveYDown);
+        let builder = |text| Builder::new(font, text)
+            .size(self.size)
+            .color(self.text_color)
+            .halign(HorizontalAlign::Center)
+            .valign(VerticalAlign::MiddleCapHeight);
+        // measured the way the text will be laid out, so it fits its segment
+        let label_width = (builder(self.label).max_content_width(&mut layout) + 2.0 * self.padding).ceil();
+        let value_width = (builder(self.value).max_content_width(&mut layout) + 2.0 * self.padding).ceil();
+        let (width, height) = ((label_width + value_width) as u32, self.height.ceil() as u32);
+        let mut pixmap = Pixmap::new(width, height).ok_or(Error::Canvas { width, height })?;
+        let rect = |x, width| Rect::from_xywh(x, 0.0, width, self.height).ok_or(Error::Rect { x, y: 0.0, width, height: self.height });
+        let label_rect = rect(0.0, label_width)?;
+        let value_rect = rect(label_width, value_width)?;
+        let radius = self.corner_radius;
+        for (text, segment, background, radii) in [
+            (self.label, label_rect, self.label_background, [radius, 0.0, 0.0, radius]),
+            (self.value, value_rect, self.value_background, [0.0, radius, radius, 0.0]),
+        ] {
+            if let Some(path) = rounded_rect(segment, radii) {
+                let mut paint = Paint::default();
+                paint.set_color(Color::from_rgba8(background.red(), background.green(), background.blue(), background.alpha()));
+                pixmap.fill_path(&path, &paint, FillRule::Winding, Transform::identity(), None);
+            }
+            builder(text)
+                .bounds_inner(segment)
+                .build(&mut layout)
+                .draw(pixmap.as_mut(), glyph_cache)?;
+        }
+        Ok(pixmap)
+    }
+}
@@ -0,0 +1,103 @@
+#![allow(unused_crate_dependencies)]
+
+use {
+    std::path::PathBuf,
+    fontdue::layout::{
+        CoordinateSystem,
+        Layout,
+    },
+    tiny_skia::Pixmap,
+    text::{
+        Builder,
+        GlyphCache,
+        TryIntoColor as _,
+        VerticalAlign,
+    },
+};
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum HAlign {
+    Left,
+    Center,
+    Right,
+    Start,
+    End,
+}
+
+impl From<HAlign> for text::HAlign {
+    fn from(halign: HAlign) -> Self {
+        match halign {
+            HAlign::Left => Self::Left,
+            HAlign::Center => Self::Center,
+            HAlign::Right => Self::Right,
+            HAlign::Start => Self::Start,
+            HAlign::End => Self::End,
+        }
+    }
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum VAlign {
+    Top,
+    Middle,
+    Bottom,
+}
+
+impl From<VAlign> for VerticalAlign {
+    fn from(valign: VAlign) -> Self {
+        match valign {
+            VAlign::Top => Self::Top,
+            VAlign::Middle => Self::Middle,
+            VAlign::Bottom => Self::Bottom,
+        }
+    }
+}
+
+#[derive(clap::Parser)]
+struct Args {
+    font: PathBuf,
+    text: String,
+    output: PathBuf,
+    #[clap(long, default_value_t = text::DEFAULT_SIZE)]
+    size: f32,
+    #[clap(long, default_value = "#ffffff")]
+    color: String,
+    #[clap(long, default_value = "#00000000")]
+    background: String,
+    #[clap(long, default_value_t = 512)]
+    width: u32,
+    #[clap(long, default_value_t = 256)]
+    height: u32,
+    #[clap(long, value_enum, default_value_t = HAlign::Center)]
+    halign: HAlign,
+    #[clap(long, value_enum, default_value_t = VAlign::Middle)]
+    valign: VAlign,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum Error {
+    #[error(transparent)]
+    Encode(#[from] png::EncodingError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Text(#[from] text::Error),
+}
+
+fn main() -> Result<(), Error> {
+    let args = <Args as clap::Parser>::parse();
+    let font = text::font_from_bytes(&std::fs::read(&args.font)?)?;
+    let mut canvas = Pixmap::new(args.width, args.height).ok_or(text::Error::Canvas { width: args.width, height: args.height })?;
+    let background = args.background.as_str().try_into_color_u8()?;
+    canvas.fill(tiny_skia::Color::from_rgba8(background.red(), background.green(), background.blue(), background.alpha()));
+    let mut layout = Layout::new(CoordinateSystem::PositiveYDown);
+    Builder::new(&font, &args.text)
+        .try_color(args.color.as_str())?
+        .size(args.size)
+        .halign(args.halign)
+        .valign(VerticalAlign::from(args.valign))
+        .build(&mut layout, [args.width as f32, args.height as f32])?
+        .draw(canvas.as_mut(), &mut GlyphCache::default())?;
+    canvas.save_png(&args.output)?;
+    Ok(())
+}
@@ -0,0 +1,150 @@
+use {
+    alloc::{
+        string::String,
+        vec::Vec,
+    },
+    core::fmt::Write as _,
+    fontdue::{
+        Font,
+        layout::GlyphRasterConfig,
+    },
+    itertools::Itertools as _,
+    tiny_skia::{
+        Pixmap,
+        PixmapPaint,
+        Transform,
+    },
+    crate::{
+        Antialiasing,
+        Error,
+        GlyphKey,
+        Hinting,
+        msdf_glyph,
+        rasterize,
+        unique,
+    },
+};
+#[cfg(not(any(feature = "std", test)))] use crate::float::Float as _;
+
+const PADDING: u32 = 1;
+
+pub struct BmFont {
+    pub atlas: Pixmap,
+    pub fnt: String,
+}
+
+struct AtlasGlyph {
+    c: char,
+    pixmap: Option<Pixmap>,
+    xoffset: i32,
+    top: i32,
+    xadvance: f32,
+}
+
+impl AtlasGlyph {
+    fn size(&self) -> (u32, u32) {
+        self.pixmap.as_ref().map_or((0, 0), |pixmap| (pixmap.width(), pixmap.height()))
+    }
+}
+
+pub fn export_bmfont(font: &Font, size: f32, charset: &str, page_file: &str) -> Result<BmFont, Error> {
+    export(font, size, charset, page_file, |c| {
+        let metrics = font.metrics(c, size);
+        let pixmap = if metrics.width > 0 && metrics.height > 0 {
+            let key = GlyphKey {
+                config: GlyphRasterConfig { glyph_index: font.lookup_glyph_index(c), px: size, font_hash: font.file_hash() },
+                color: [u8::MAX; 4],
+                hinting: Hinting::None,
+                antialiasing: Antialiasing::Grayscale,
+                stroke: 0,
+                scale_x: 1.0f32.to_bits(),
+            };
+            Some(rasterize(font, 0, [metrics.width, metrics.height], key)?)
+        } else {
+            None
+        };
+        Ok(AtlasGlyph {
+            xoffset: metrics.xmin,
+            top: metrics.ymin + metrics.height as i32,
+            xadvance: metrics.advance_width,
+            c, pixmap,
+        })
+    })
+}
+
+/// Like `export_bmfont`, but the atlas contains multi-channel signed distance fields with the given pixel range.
+pub fn export_bmfont_msdf(font: &Font, font_data: &[u8], size: f32, range: f32, charset: &str, page_file: &str) -> Result<BmFont, Error> {
+    let face = ttf_parser::Face::parse(font_data, 0)?;
+    let scale = size / f32::from(face.units_per_em());
+    export(font, size, charset, page_file, |c| {
+        let glyph_index = font.lookup_glyph_index(c);
+        let bbox = face.glyph_bounding_box(ttf_parser::GlyphId(glyph_index));
+        Ok(AtlasGlyph {
+            pixmap: msdf_glyph(font_data, glyph_index, size, range)?,
+            xoffset: bbox.map_or(0, |bbox| (f32::from(bbox.x_min) * scale - range).round() as i32),
+            top: bbox.map_or(0, |bbox| (f32::from(bbox.y_max) * scale + range).round() as i32),
+            xadvance: font.metrics_indexed(glyph_index, size).advance_width,
+            c,
+        })
+    })
+}
+
+fn export(font: &Font, size: f32, charset: &str, page_file: &str, render: impl Fn(char) -> Result<AtlasGlyph, Error>) -> Result<BmFont, Error> {
+    let chars = unique(charset.chars()).filter(|c| !c.is_control()).collect_vec();
+    let glyphs = chars.iter().map(|&c| render(c)).collect::<Result<Vec<_>, Error>>()?;
+    let area = glyphs.iter().map(|glyph| {
+        let (width, height) = glyph.size();
+        (width + PADDING) * (height + PADDING)
+    }).sum::<u32>();
+    let max_width = glyphs.iter().map(|glyph| glyph.size().0 + PADDING).max().unwrap_or(1);
+    let atlas_width = (area as f32).sqrt().ceil().max(max_width as f32).max(1.0) as u32;
+    let atlas_width = atlas_width.next_power_of_two();
+    let mut positions = Vec::with_capacity(glyphs.len());
+    let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+    for glyph in &glyphs {
+        let (width, height) = glyph.size();
+        if x + width > atlas_width {
+            x = 0;
+            y += shelf_height + PADDING;
+            shelf_height = 0;
+        }
+        positions.push((x, y));
+        x += width + PADDING;
+        shelf_height = shelf_height.max(height);
+    }
+    let atlas_height = (y + shelf_height).max(1).next_power_of_two();
+    let mut atlas = Pixmap::new(atlas_width, atlas_height).ok_or(Error::Canvas { width: atlas_width, height: atlas_height })?;
+    for (glyph, &(x, y)) in glyphs.iter().zip(&positions) {
+        if let Some(ref pixmap) = glyph.pixmap {
+            atlas.draw_pixmap(x as i32, y as i32, pixmap.as_ref(), &PixmapPaint::default(), Transform::identity(), None);
+        }
+    }
+    let line_metrics = font.horizontal_line_metrics(size);
+    let base = line_metrics.map_or(size, |line_metrics| line_metrics.ascent).ceil();
+    let line_height = line_metrics.map_or(size, |line_metrics| line_metrics.new_line_size).ceil();
+    let mut fnt = String::default();
+    let _ = writeln!(fnt, "info face={:?} size={} bold=0 italic=0 charset=\"\" unicode=1 stretchH=100 smooth=1 aa=1 padding=0,0,0,0 spacing={PADDING},{PADDING}", font.name().unwrap_or_default(), size.round());
+    let _ = writeln!(fnt, "common lineHeight={line_height} base={base} scaleW={atlas_width} scaleH={atlas_height} pages=1 packed=0");
+    let _ = writeln!(fnt, "page id=0 file={page_file:?}");
+    let _ = writeln!(fnt, "chars count={}", glyphs.len());
+    for (glyph, (x, y)) in glyphs.iter().zip(positions) {
+        let (width, height) = glyph.size();
+        let _ = writeln!(fnt,
+            "char id={} x={x} y={y} width={width} height={height} xoffset={} yoffset={} xadvance={} page=0 chnl=15",
+            u32::from(glyph.c),
+            glyph.xoffset,
+            base as i32 - glyph.top,
+            glyph.xadvance.round(),
+        );
+    }
+    let kernings = chars.iter()
+        .cartesian_product(&chars)
+        .filter_map(|(&first, &second)| Some((first, second, font.horizontal_kern(first, second, size)?.round())))
+        .filter(|&(_, _, amount)| amount != 0.0)
+        .collect_vec();
+    let _ = writeln!(fnt, "kernings count={}", kernings.len());
+    for (first, second, amount) in kernings {
+        let _ = writeln!(fnt, "kerning first={} second={} amount={amount}", u32::from(first), u32::from(second));
+    }
+    Ok(BmFont { atlas, fnt })
+}
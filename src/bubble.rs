@@ -0,0 +1,91 @@
+use {
+    tiny_skia::{
+        ColorU8,
+        FillRule,
+        LineJoin,
+        PathBuilder,
+        PixmapMut,
+        Rect,
+        Stroke,
+        Transform,
+    },
+    crate::{
+        Error,
+        GlyphCache,
+        TextBox,
+        rounded_rect,
+        union,
+    },
+};
+
+/// A speech bubble or callout drawn behind a text box by `TextBox::draw_bubble`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bubble {
+    pub fill: ColorU8,
+    pub border: Option<(ColorU8, f32)>,
+    /// Space between the text and the edge of the bubble.
+    pub padding: f32,
+    pub corner_radius: f32,
+    /// The point the tail points at, if any.
+    pub anchor: Option<[f32; 2]>,
+    /// Width of the tail where it meets the bubble.
+    pub tail_width: f32,
+}
+
+impl Default for Bubble {
+    fn default() -> Self {
+        Self {
+            fill: ColorU8::from_rgba(u8::MAX, u8::MAX, u8::MAX, u8::MAX),
+            border: Some((ColorU8::from_rgba(0, 0, 0, u8::MAX), 2.0)),
+            padding: 12.0,
+            corner_radius: 12.0,
+            anchor: None,
+            tail_width: 16.0,
+        }
+    }
+}
+
+impl TextBox<'_, '_> {
+    /// Draws a bubble sized to fit the text, then the text itself.
+    pub fn draw_bubble(&self, mut canvas: PixmapMut<'_>, glyph_cache: &mut GlyphCache, bubble: &Bubble) -> Result<Option<Rect>, Error> {
+        let text_rect = self.rect_inner()?;
+        let rect = text_rect.outset(bubble.padding, bubble.padding).ok_or(Error::Outset { rect: text_rect, amount: bubble.padding })?;
+        let mut path = PathBuilder::new();
+        if let Some(body) = rounded_rect(rect, [bubble.corner_radius; 4]) {
+            path.push_path(&body);
+        }
+        let mut dirty = Some(rect);
+        if let Some([anchor_x, anchor_y]) = bubble.anchor.filter(|&[x, y]| x < rect.left() || x > rect.right() || y < rect.top() || y > rect.bottom()) {
+            // the tail starts inside the body so the fill hides the seam
+            let inset = bubble.corner_radius.max(bubble.tail_width / 2.0).min(rect.width() / 2.0).min(rect.height() / 2.0);
+            let base_x = anchor_x.clamp(rect.left() + inset, rect.right() - inset);
+            let base_y = anchor_y.clamp(rect.top() + inset, rect.bottom() - inset);
+            let half = bubble.tail_width / 2.0;
+            let ((x1, y1), (x2, y2)) = if anchor_y > rect.bottom() || anchor_y < rect.top() {
+                let y = if anchor_y > rect.bottom() { rect.bottom() - inset } else { rect.top() + inset };
+                ((base_x - half, y), (base_x + half, y))
+            } else {
+                let x = if anchor_x > rect.right() { rect.right() - inset } else { rect.left() + inset };
+                ((x, base_y - half), (x, base_y + half))
+            };
+            // wind the tail the same way as the body so the overlap isn't cut out of the fill
+            let ((x1, y1), (x2, y2)) = if (anchor_x - x1) * (y2 - y1) - (anchor_y - y1) * (x2 - x1) > 0.0 { ((x1, y1), (x2, y2)) } else { ((x2, y2), (x1, y1)) };
+            path.move_to(x1, y1);
+            path.line_to(anchor_x, anchor_y);
+            path.line_to(x2, y2);
+            path.close();
+            dirty = union(dirty, Rect::from_ltrb(anchor_x.min(rect.left()), anchor_y.min(rect.top()), anchor_x.max(rect.right()), anchor_y.max(rect.bottom())));
+        }
+        if let Some(path) = path.finish() {
+            if let Some((color, width)) = bubble.border {
+                // stroked at double width, then covered by the fill, so only the outside half remains and the tail seam is hidden
+                let stroke = Stroke { width: 2.0 * width, line_join: LineJoin::Round, ..Stroke::default() };
+                canvas.stroke_path(&path, &self.paint(color), &stroke, Transform::identity(), None);
+                dirty = dirty.and_then(|dirty| dirty.outset(width, width));
+            }
+            canvas.fill_path(&path, &self.paint(bubble.fill), FillRule::Winding, Transform::identity(), None);
+        }
+        dirty = union(dirty, self.draw(canvas, glyph_cache)?);
+        Ok(dirty)
+    }
+}
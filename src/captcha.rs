@@ -0,0 +1,118 @@
+use {
+    tiny_skia::{
+        FilterQuality,
+        PathBuilder,
+        PixmapMut,
+        PixmapPaint,
+        Rect,
+        Stroke,
+        Transform,
+    },
+    crate::{
+        Error,
+        GlyphCache,
+        TextBox,
+        union,
+    },
+};
+#[cfg(not(any(feature = "std", test)))] use crate::float::Float as _;
+
+/// Settings for `TextBox::draw_distorted`. The same seed always produces the same image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Distortion {
+    pub seed: u64,
+    /// Maximum rotation of each glyph, in degrees.
+    pub rotation: f32,
+    /// Maximum offset of each glyph in either direction, in pixels.
+    pub jitter: f32,
+    pub wave_amplitude: f32,
+    pub wave_length: f32,
+    /// Fraction of pixels in the text area to cover with specks of the text color.
+    pub noise: f32,
+    /// Number of random curves drawn across the text.
+    pub lines: u32,
+}
+
+impl Default for Distortion {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            rotation: 20.0,
+            jitter: 2.0,
+            wave_amplitude: 4.0,
+            wave_length: 80.0,
+            noise: 0.02,
+            lines: 2,
+        }
+    }
+}
+
+/// splitmix64, which is plenty for visual noise and keeps the output stable across platforms.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32 * (max - min)
+    }
+}
+
+impl TextBox<'_, '_> {
+    pub fn draw_distorted(&self, mut canvas: PixmapMut<'_>, glyph_cache: &mut GlyphCache, distortion: &Distortion) -> Result<Option<Rect>, Error> {
+        if let Some((c, byte_offset)) = self.control_char {
+            return Err(Error::ControlChar { c, byte_offset })
+        }
+        let mut rng = Rng(distortion.seed);
+        let paint = PixmapPaint {
+            opacity: self.opacity.clamp(0.0, 1.0),
+            blend_mode: self.blend_mode,
+            quality: FilterQuality::Bilinear,
+        };
+        let mut dirty = None;
+        for (idx, glyph) in self.glyphs.iter().enumerate() {
+            if glyph.width == 0 || glyph.height == 0 { continue }
+            let (width, height) = (glyph.width as f32, glyph.height as f32);
+            let center_x = glyph.x + width / 2.0;
+            let wave = if distortion.wave_length > 0.0 {
+                distortion.wave_amplitude * (center_x / distortion.wave_length * core::f32::consts::TAU).sin()
+            } else {
+                0.0
+            };
+            let x = glyph.x + rng.range(-distortion.jitter, distortion.jitter);
+            let y = glyph.y + wave + rng.range(-distortion.jitter, distortion.jitter);
+            let transform = Transform::from_rotate_at(rng.range(-distortion.rotation, distortion.rotation), x + width / 2.0, y + height / 2.0).pre_translate(x, y);
+            let glyph_canvas = self.cached_glyph(glyph, self.glyph_key(idx), glyph_cache)?;
+            canvas.draw_pixmap(0, 0, glyph_canvas.as_ref(), &paint, transform, None);
+            let reach = width.hypot(height) / 2.0;
+            dirty = union(dirty, Rect::from_ltrb(x + width / 2.0 - reach, y + height / 2.0 - reach, x + width / 2.0 + reach, y + height / 2.0 + reach));
+        }
+        let Some(area) = dirty else { return Ok(None) };
+        let ink = self.paint(self.color);
+        for _ in 0..distortion.lines {
+            let mut path = PathBuilder::new();
+            path.move_to(area.left(), rng.range(area.top(), area.bottom()));
+            path.cubic_to(
+                rng.range(area.left(), area.right()), rng.range(area.top(), area.bottom()),
+                rng.range(area.left(), area.right()), rng.range(area.top(), area.bottom()),
+                area.right(), rng.range(area.top(), area.bottom()),
+            );
+            if let Some(path) = path.finish() {
+                canvas.stroke_path(&path, &ink, &Stroke { width: (self.size / 20.0).max(1.0), ..Stroke::default() }, Transform::identity(), None);
+            }
+        }
+        let specks = (distortion.noise.clamp(0.0, 1.0) * area.width() * area.height()) as usize;
+        for _ in 0..specks {
+            if let Some(speck) = Rect::from_xywh(rng.range(area.left(), area.right()).floor(), rng.range(area.top(), area.bottom()).floor(), 1.0, 1.0) {
+                canvas.fill_rect(speck, &ink, Transform::identity(), None);
+            }
+        }
+        Ok(area.intersect(&Rect::from_xywh(0.0, 0.0, canvas.width() as f32, canvas.height() as f32).ok_or(Error::Canvas { width: canvas.width(), height: canvas.height() })?))
+    }
+}
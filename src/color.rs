@@ -0,0 +1,102 @@
+use {
+    tiny_skia::{
+        Color,
+        ColorU8,
+    },
+    crate::IntoColor,
+};
+#[cfg(not(any(feature = "std", test)))] use crate::float::Float as _;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsl {
+    pub hue: f32,
+    pub saturation: f32,
+    pub lightness: f32,
+    pub alpha: f32,
+}
+
+impl Hsl {
+    pub fn new(hue: f32, saturation: f32, lightness: f32) -> Self {
+        Self { hue, saturation, lightness, alpha: 1.0 }
+    }
+}
+
+impl IntoColor for Hsl {
+    fn into_color_u8(self) -> ColorU8 {
+        let chroma = (1.0 - (2.0 * self.lightness - 1.0).abs()) * self.saturation;
+        let [red, green, blue] = from_hue(self.hue, chroma, self.lightness - chroma / 2.0);
+        rgba(red, green, blue, self.alpha)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsv {
+    pub hue: f32,
+    pub saturation: f32,
+    pub value: f32,
+    pub alpha: f32,
+}
+
+impl Hsv {
+    pub fn new(hue: f32, saturation: f32, value: f32) -> Self {
+        Self { hue, saturation, value, alpha: 1.0 }
+    }
+}
+
+impl IntoColor for Hsv {
+    fn into_color_u8(self) -> ColorU8 {
+        let chroma = self.value * self.saturation;
+        let [red, green, blue] = from_hue(self.hue, chroma, self.value - chroma);
+        rgba(red, green, blue, self.alpha)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklch {
+    pub lightness: f32,
+    pub chroma: f32,
+    pub hue: f32,
+    pub alpha: f32,
+}
+
+impl Oklch {
+    pub fn new(lightness: f32, chroma: f32, hue: f32) -> Self {
+        Self { lightness, chroma, hue, alpha: 1.0 }
+    }
+}
+
+impl IntoColor for Oklch {
+    fn into_color_u8(self) -> ColorU8 {
+        let (sin, cos) = self.hue.to_radians().sin_cos();
+        let (a, b) = (self.chroma * cos, self.chroma * sin);
+        let l = (self.lightness + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+        let m = (self.lightness - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+        let s = (self.lightness - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+        let [red, green, blue] = [
+            4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s,
+            -1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s,
+            -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s,
+        ].map(|linear| if linear <= 0.003_130_8 { 12.92 * linear } else { 1.055 * linear.powf(1.0 / 2.4) - 0.055 });
+        rgba(red, green, blue, self.alpha)
+    }
+}
+
+fn from_hue(hue: f32, chroma: f32, offset: f32) -> [f32; 3] {
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let [red, green, blue] = match sector as u8 {
+        0 => [chroma, x, 0.0],
+        1 => [x, chroma, 0.0],
+        2 => [0.0, chroma, x],
+        3 => [0.0, x, chroma],
+        4 => [x, 0.0, chroma],
+        _ => [chroma, 0.0, x],
+    };
+    [red + offset, green + offset, blue + offset]
+}
+
+fn rgba(red: f32, green: f32, blue: f32, alpha: f32) -> ColorU8 {
+    Color::from_rgba(red.clamp(0.0, 1.0), green.clamp(0.0, 1.0), blue.clamp(0.0, 1.0), alpha.clamp(0.0, 1.0))
+        .unwrap_or(Color::BLACK)
+        .to_color_u8()
+}
@@ -0,0 +1,62 @@
+use {
+    alloc::vec::Vec,
+    core::hash::Hash,
+    hashbrown::HashMap,
+    tiny_skia::Rect,
+    crate::{
+        TextBox,
+        union,
+    },
+};
+
+impl TextBox<'_, '_> {
+    /// The area `draw` can touch, computed without drawing.
+    pub fn covered_rect(&self) -> Option<Rect> {
+        let background = self.background
+            .filter(|_| !self.glyphs.is_empty())
+            .and_then(|(_, padding)| self.rect_outer().ok()?.outset(padding, padding));
+        (0..self.lines.len()).fold(background, |rect, line| union(rect, self.line_rect(line)))
+    }
+}
+
+/// Remembers where each text box was drawn in the previous frame, so that only the areas that changed need to be cleared and redrawn.
+#[derive(Debug, Clone)]
+pub struct DamageTracker<K> {
+    previous: HashMap<K, Rect>,
+    current: HashMap<K, Rect>,
+}
+
+impl<K> Default for DamageTracker<K> {
+    fn default() -> Self {
+        Self {
+            previous: HashMap::default(),
+            current: HashMap::default(),
+        }
+    }
+}
+
+impl<K: Eq + Hash> DamageTracker<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records where the text box with the given id is drawn this frame and returns the area to clear and redraw, i.e. the union of its previous and current rects.
+    pub fn update(&mut self, id: K, text_box: &TextBox<'_, '_>) -> Option<Rect> {
+        self.update_rect(id, text_box.covered_rect())
+    }
+
+    pub fn update_rect(&mut self, id: K, rect: Option<Rect>) -> Option<Rect> {
+        let previous = self.previous.remove(&id);
+        if let Some(rect) = rect {
+            self.current.insert(id, rect);
+        }
+        union(previous, rect)
+    }
+
+    /// Ends the frame and returns the rects of text boxes that were drawn in the previous frame but not updated in this one, which need to be cleared.
+    pub fn end_frame(&mut self) -> Vec<Rect> {
+        let removed = self.previous.drain().map(|(_, rect)| rect).collect();
+        self.previous = core::mem::take(&mut self.current);
+        removed
+    }
+}
@@ -0,0 +1,148 @@
+use {
+    alloc::{
+        vec,
+        vec::Vec,
+    },
+    core::ops::Range,
+    tiny_skia::{
+        ColorU8,
+        LineCap,
+        Mask,
+        PathBuilder,
+        PixmapMut,
+        Rect,
+        Stroke,
+        StrokeDash,
+        Transform,
+    },
+    crate::{
+        Metrics,
+        TextBox,
+        union,
+    },
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum DecorationLine {
+    #[default]
+    Underline,
+    Overline,
+    Strikethrough,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum LineStyle {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+    Wavy,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Decoration {
+    pub line: DecorationLine,
+    pub style: LineStyle,
+    /// Defaults to the text color.
+    pub color: Option<ColorU8>,
+    /// Defaults to a sixteenth of the font size.
+    pub thickness: Option<f32>,
+}
+
+impl Decoration {
+    pub fn new(line: DecorationLine, style: LineStyle) -> Self {
+        Self { line, style, color: None, thickness: None }
+    }
+
+    pub fn color(self, color: impl crate::IntoColor) -> Self {
+        Self { color: Some(color.into_color_u8()), ..self }
+    }
+
+    pub fn thickness(self, thickness: f32) -> Self {
+        Self { thickness: Some(thickness), ..self }
+    }
+}
+
+impl TextBox<'_, '_> {
+    /// The horizontal extent of each line's visible glyphs, along with its baseline.
+    fn line_extents(&self) -> impl Iterator<Item = (f32, f32, f32)> + '_ {
+        self.lines.iter().filter_map(|line| {
+            let glyphs = self.glyphs.get(line.glyph_start..=line.glyph_end)?;
+            let (left, right) = glyphs.iter()
+                .filter(|glyph| !glyph.parent.is_whitespace())
+                .map(|glyph| self.glyph_extent(glyph))
+                .reduce(|(left1, right1), (left2, right2)| (left1.min(left2), right1.max(right2)))?;
+            Some((left, right, line.baseline_y + self.y_offset))
+        })
+    }
+
+    /// The horizontal extent of the glyphs on each line whose text starts within the given byte range, along with the line's baseline.
+    fn range_extents(&self, offsets: &[usize], range: &Range<usize>) -> Vec<(f32, f32, f32)> {
+        self.lines.iter().filter_map(|line| {
+            let (left, right) = (line.glyph_start..=line.glyph_end)
+                .filter(|&idx| offsets.get(idx).is_some_and(|offset| range.contains(offset)))
+                .map(|idx| self.glyph_extent(&self.glyphs[idx]))
+                .reduce(|(left1, right1), (left2, right2)| (left1.min(left2), right1.max(right2)))?;
+            Some((left, right, line.baseline_y + self.y_offset))
+        }).collect()
+    }
+
+    /// Draws the decorations matching `filter` and returns the area they cover.
+    pub(crate) fn draw_decorations(&self, canvas: &mut PixmapMut<'_>, transform: Transform, mask: Option<&Mask>, filter: impl Fn(DecorationLine) -> bool) -> Option<Rect> {
+        if self.decorations.is_empty() && self.decoration_spans.is_empty() { return None }
+        let metrics = Metrics::new(self.font, self.size)?;
+        let offsets = if self.decoration_spans.is_empty() { Vec::default() } else { self.glyph_byte_offsets() };
+        let mut dirty = None;
+        let decorations = self.decorations.iter()
+            .filter(|decoration| filter(decoration.line))
+            .map(|decoration| (decoration, self.line_extents().collect()))
+            .chain(self.decoration_spans.iter()
+                .filter(|(_, decoration)| filter(decoration.line))
+                .map(|(range, decoration)| (decoration, self.range_extents(&offsets, range))));
+        for (decoration, extents) in decorations {
+            let thickness = decoration.thickness.unwrap_or(self.size / 16.0).max(1.0);
+            let paint = self.paint(decoration.color.unwrap_or(self.color));
+            let mut stroke = Stroke { width: thickness, ..Stroke::default() };
+            match decoration.style {
+                LineStyle::Solid | LineStyle::Wavy => {}
+                LineStyle::Dashed => stroke.dash = StrokeDash::new(vec![3.0 * thickness, 2.0 * thickness], 0.0),
+                LineStyle::Dotted => {
+                    stroke.line_cap = LineCap::Round;
+                    stroke.dash = StrokeDash::new(vec![0.0, 2.0 * thickness], 0.0);
+                }
+            }
+            for (left, right, baseline) in extents {
+                let y = baseline + match decoration.line {
+                    DecorationLine::Underline => self.size / 10.0 + thickness / 2.0,
+                    DecorationLine::Overline => -metrics.ascent,
+                    DecorationLine::Strikethrough => -metrics.x_height / 2.0,
+                };
+                let mut path = PathBuilder::new();
+                path.move_to(left, y);
+                let amplitude = if let LineStyle::Wavy = decoration.style {
+                    let amplitude = thickness * 1.5;
+                    let half_wavelength = thickness * 3.0;
+                    let mut x = left;
+                    let mut up = true;
+                    while x < right {
+                        let next = (x + half_wavelength).min(right);
+                        path.quad_to((x + next) / 2.0, if up { y - 2.0 * amplitude } else { y + 2.0 * amplitude }, next, y);
+                        x = next;
+                        up = !up;
+                    }
+                    amplitude
+                } else {
+                    path.line_to(right, y);
+                    0.0
+                };
+                let Some(path) = path.finish() else { continue };
+                canvas.stroke_path(&path, &paint, &stroke, transform, mask);
+                let reach = amplitude + thickness;
+                dirty = union(dirty, Rect::from_ltrb(left - thickness, y - reach, right + thickness, y + reach).and_then(|rect| rect.transform(transform)));
+            }
+        }
+        dirty
+    }
+}
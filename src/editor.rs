@@ -0,0 +1,195 @@
+use {
+    alloc::{
+        string::String,
+        vec::Vec,
+    },
+    core::ops::Range,
+    fontdue::{
+        Font,
+        layout::HorizontalAlign,
+    },
+    tiny_skia::Rect,
+    crate::{
+        Builder,
+        DefaultBounds,
+        Metrics,
+        TextBox,
+        VerticalAlign,
+        normalize_newlines,
+    },
+};
+#[cfg(not(any(feature = "std", test)))] use crate::float::Float as _;
+
+/// A text buffer with a cursor and selection, for simple text fields.
+///
+/// Offsets are in bytes. Newlines are normalized on input so that offsets into the buffer match the byte offsets of the laid out text,
+/// as long as the text box is built with the default handling of control characters and missing glyphs.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Editor {
+    text: String,
+    cursor: usize,
+    anchor: Option<usize>,
+}
+
+impl Editor {
+    /// Creates an editor with the cursor at the end of the text.
+    pub fn new(text: &str) -> Self {
+        let text = normalize_newlines(text).into_owned();
+        Self { cursor: text.len(), anchor: None, text }
+    }
+
+    pub fn text(&self) -> &str { &self.text }
+    pub fn cursor(&self) -> usize { self.cursor }
+
+    /// The selected byte range, if it's not empty.
+    pub fn selection(&self) -> Option<Range<usize>> {
+        let anchor = self.anchor?;
+        (anchor != self.cursor).then(|| anchor.min(self.cursor)..anchor.max(self.cursor))
+    }
+
+    pub fn selected_text(&self) -> &str {
+        self.selection().map_or("", |selection| &self.text[selection])
+    }
+
+    /// Moves the cursor to the given offset, rounded down to a character boundary. If `extend` is true, the selection is extended to the new position instead of cleared.
+    pub fn set_cursor(&mut self, offset: usize, extend: bool) {
+        let mut offset = offset.min(self.text.len());
+        while !self.text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        if extend {
+            self.anchor.get_or_insert(self.cursor);
+        } else {
+            self.anchor = None;
+        }
+        self.cursor = offset;
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = Some(0);
+        self.cursor = self.text.len();
+    }
+
+    /// Moves the cursor one character back. Without `extend`, a selection is collapsed to its start instead.
+    pub fn move_left(&mut self, extend: bool) {
+        match self.selection() {
+            Some(selection) if !extend => self.set_cursor(selection.start, false),
+            _ => self.set_cursor(self.text[..self.cursor].char_indices().next_back().map_or(0, |(offset, _)| offset), extend),
+        }
+    }
+
+    /// Moves the cursor one character forward. Without `extend`, a selection is collapsed to its end instead.
+    pub fn move_right(&mut self, extend: bool) {
+        match self.selection() {
+            Some(selection) if !extend => self.set_cursor(selection.end, false),
+            _ => self.set_cursor(self.text[self.cursor..].chars().next().map_or(self.cursor, |c| self.cursor + c.len_utf8()), extend),
+        }
+    }
+
+    /// Replaces the selection, if any, with the given text and places the cursor after it.
+    pub fn insert(&mut self, text: &str) {
+        let text = normalize_newlines(text);
+        let range = self.selection().unwrap_or(self.cursor..self.cursor);
+        self.text.replace_range(range.clone(), &text);
+        self.anchor = None;
+        self.cursor = range.start + text.len();
+    }
+
+    /// Deletes the selection, or the character before the cursor.
+    pub fn backspace(&mut self) {
+        if self.selection().is_none() {
+            self.move_left(true);
+        }
+        self.insert("");
+    }
+
+    /// Deletes the selection, or the character after the cursor.
+    pub fn delete(&mut self) {
+        if self.selection().is_none() {
+            self.move_right(true);
+        }
+        self.insert("");
+    }
+
+    /// Moves the cursor to the position closest to a point in a text box built from this editor.
+    pub fn click(&mut self, text_box: &TextBox<'_, '_>, x: f32, y: f32, extend: bool) {
+        self.set_cursor(text_box.offset_at(x, y), extend);
+    }
+
+    /// Starts building a text box for the current text.
+    pub fn builder<'f>(&self, font: &'f Font) -> Builder<'f, '_, DefaultBounds> {
+        Builder::new(font, &self.text)
+    }
+
+    /// The caret's rectangle in a text box built from this editor.
+    pub fn caret_rect(&self, text_box: &TextBox<'_, '_>) -> Option<Rect> {
+        text_box.caret_rect(self.cursor)
+    }
+
+    /// The rectangles to highlight for the selection in a text box built from this editor.
+    pub fn selection_rects(&self, text_box: &TextBox<'_, '_>) -> Vec<Rect> {
+        self.selection().map_or_else(Vec::default, |selection| text_box.rects_for_range(selection))
+    }
+}
+
+impl TextBox<'_, '_> {
+    /// The byte offset of the caret position closest to the given point.
+    pub fn offset_at(&self, x: f32, y: f32) -> usize {
+        let offsets = self.glyph_byte_offsets();
+        let Some(line) = self.lines.iter()
+            .find(|line| y < line.baseline_y + self.y_offset - line.min_descent)
+            .or(self.lines.last())
+        else { return 0 };
+        let end = |idx: usize| offsets.get(idx).map_or(0, |&offset| offset + self.glyphs[idx].parent.len_utf8());
+        // a line without glyphs follows a trailing newline
+        let Some(glyphs) = self.glyphs.get(line.glyph_start..=line.glyph_end) else { return self.glyphs.len().checked_sub(1).map_or(0, end) };
+        for (idx, glyph) in (line.glyph_start..).zip(glyphs) {
+            let (left, right) = self.glyph_extent(glyph);
+            if glyph.parent == '\n' || x < (left + right) / 2.0 { return offsets[idx] }
+        }
+        end(line.glyph_end)
+    }
+
+    /// A rectangle for drawing the caret in front of the given byte offset, spanning the height of its line. It is aligned to whole pixels so it stays crisp.
+    pub fn caret_rect(&self, offset: usize) -> Option<Rect> {
+        let width = (self.size / 16.0).round().max(1.0);
+        let align = match self.halign {
+            HorizontalAlign::Left => 0.0,
+            HorizontalAlign::Center => 0.5,
+            HorizontalAlign::Right => 1.0,
+        };
+        let offsets = self.glyph_byte_offsets();
+        let found = self.lines.iter().find_map(|line| (line.glyph_start..=line.glyph_end)
+            .find(|&idx| offsets.get(idx) == Some(&offset))
+            .map(|idx| (line, self.glyph_extent(&self.glyphs[idx]).0))
+        );
+        let (line, x) = if let Some(found) = found {
+            found
+        } else if let Some(line) = self.lines.last() {
+            // the caret is at the end of the text
+            match self.glyphs.get(line.glyph_start..=line.glyph_end).and_then(|glyphs| glyphs.last()) {
+                Some(glyph) if glyph.parent == '\n' => {
+                    // the layout has no line after a trailing newline, so place the caret below the last line
+                    let x = self.inner_bounds.x() + (self.inner_bounds.width() * align).floor();
+                    let rect = self.span_rect(line, x, x)?;
+                    return Rect::from_xywh((x - width / 2.0).round(), (rect.y() + line.max_new_line_size).round(), width, rect.height().round())
+                }
+                Some(glyph) => (line, self.glyph_extent(glyph).1),
+                None => (line, self.inner_bounds.x() + (line.padding * align).floor()),
+            }
+        } else {
+            // there is no text, so place the caret where the first line would be
+            let metrics = Metrics::new(self.font, self.size)?;
+            let height = metrics.ascent - metrics.descent;
+            let top = self.inner_bounds.y() + match self.valign {
+                VerticalAlign::Top | VerticalAlign::Baseline(_) => 0.0,
+                VerticalAlign::Middle | VerticalAlign::MiddleCapHeight | VerticalAlign::MiddleXHeight => (self.inner_bounds.height() - height) / 2.0,
+                VerticalAlign::Bottom => self.inner_bounds.height() - height,
+            };
+            let x = self.inner_bounds.x() + (self.inner_bounds.width() * align).floor();
+            return Rect::from_xywh((x - width / 2.0).round(), top.round(), width, height.round())
+        };
+        let rect = self.span_rect(line, x, x)?;
+        Rect::from_xywh((x - width / 2.0).round(), rect.y().round(), width, rect.height().round())
+    }
+}
@@ -0,0 +1,170 @@
+use {
+    alloc::vec::Vec,
+    tiny_skia::{
+        BlendMode,
+        ColorU8,
+        FilterQuality,
+        Mask,
+        Paint,
+        Pattern,
+        Pixmap,
+        PixmapMut,
+        PixmapPaint,
+        PremultipliedColorU8,
+        Rect,
+        SpreadMode,
+        Transform,
+    },
+    crate::{
+        Error,
+        GlyphCache,
+        GlyphKey,
+        PatternFit,
+        TextBox,
+        union,
+    },
+};
+#[cfg(not(any(feature = "std", test)))] use crate::float::Float as _;
+
+impl TextBox<'_, '_> {
+    fn glyph_bounds(&self) -> Option<Rect> {
+        self.glyphs.iter()
+            .filter(|glyph| glyph.width > 0 && glyph.height > 0)
+            .fold(None, |bounds, glyph| union(bounds, Rect::from_xywh(glyph.x, glyph.y, glyph.width as f32, glyph.height as f32)))
+    }
+
+    /// Renders every glyph in the given color onto a transparent pixmap covering the text plus `padding` on each side.
+    fn coverage_layer(&self, glyph_cache: &mut GlyphCache, color: ColorU8, padding: f32) -> Result<Option<(Pixmap, f32, f32)>, Error> {
+        let Some(bounds) = self.glyph_bounds() else { return Ok(None) };
+        let (left, top) = ((bounds.left() - padding).floor(), (bounds.top() - padding).floor());
+        let width = ((bounds.right() + padding).ceil() - left) as u32;
+        let height = ((bounds.bottom() + padding).ceil() - top) as u32;
+        let mut layer = Pixmap::new(width, height).ok_or(Error::Canvas { width, height })?;
+        for (idx, glyph) in self.glyphs.iter().enumerate().filter(|(_, glyph)| glyph.width > 0 && glyph.height > 0) {
+            let key = GlyphKey {
+                color: [color.red(), color.green(), color.blue(), color.alpha()],
+                ..self.glyph_key(idx)
+            };
+            let glyph_canvas = self.cached_glyph(glyph, key, glyph_cache)?;
+            layer.draw_pixmap(0, 0, glyph_canvas.as_ref(), &PixmapPaint::default(), Transform::from_translate(glyph.x - left, glyph.y - top), None);
+        }
+        Ok(Some((layer, left, top)))
+    }
+
+    fn draw_layer(&self, canvas: &mut PixmapMut<'_>, layer: &Pixmap, left: f32, top: f32, transform: Transform, mask: Option<&Mask>) {
+        let paint = PixmapPaint {
+            opacity: self.opacity.clamp(0.0, 1.0),
+            blend_mode: self.blend_mode,
+            ..PixmapPaint::default()
+        };
+        canvas.draw_pixmap(left as i32, top as i32, layer.as_ref(), &paint, transform, mask);
+    }
+
+    /// Draws the blurred glow behind the text, if any, and returns the area it covers.
+    pub(crate) fn draw_glow(&self, canvas: &mut PixmapMut<'_>, glyph_cache: &mut GlyphCache, transform: Transform, mask: Option<&Mask>) -> Result<Option<Rect>, Error> {
+        let Some((color, radius)) = self.glow else { return Ok(None) };
+        let Some((mut layer, left, top)) = self.coverage_layer(glyph_cache, color, radius.ceil())? else { return Ok(None) };
+        // three box blur passes approximate a gaussian blur
+        let box_radius = (radius / 3.0).round().max(1.0) as usize;
+        for _ in 0..3 {
+            box_blur(&mut layer, box_radius);
+        }
+        self.draw_layer(canvas, &layer, left, top, transform, mask);
+        Ok(Rect::from_xywh(left, top, layer.width() as f32, layer.height() as f32).and_then(|rect| rect.transform(transform)))
+    }
+
+    /// Draws the fill pattern, if any, masked by the glyph coverage.
+    pub(crate) fn draw_pattern(&self, canvas: &mut PixmapMut<'_>, glyph_cache: &mut GlyphCache, transform: Transform, mask: Option<&Mask>) -> Result<(), Error> {
+        let Some((pattern, fit)) = self.pattern else { return Ok(()) };
+        let (Some(bounds), Some((mut layer, left, top))) = (self.glyph_bounds(), self.coverage_layer(glyph_cache, ColorU8::from_rgba(0, 0, 0, u8::MAX), 0.0)?) else { return Ok(()) };
+        let pattern_transform = match fit {
+            PatternFit::Tile => Transform::from_translate(bounds.left() - left, bounds.top() - top),
+            PatternFit::Stretch => Transform::from_row(
+                bounds.width() / pattern.width() as f32, 0.0,
+                0.0, bounds.height() / pattern.height() as f32,
+                bounds.left() - left, bounds.top() - top,
+            ),
+        };
+        let paint = Paint {
+            shader: Pattern::new(pattern.as_ref(), if let PatternFit::Tile = fit { SpreadMode::Repeat } else { SpreadMode::Pad }, FilterQuality::Bilinear, 1.0, pattern_transform),
+            blend_mode: BlendMode::SourceIn,
+            ..Paint::default()
+        };
+        let rect = Rect::from_xywh(0.0, 0.0, layer.width() as f32, layer.height() as f32).ok_or(Error::Canvas { width: layer.width(), height: layer.height() })?;
+        layer.fill_rect(rect, &paint, Transform::identity(), None);
+        self.draw_layer(canvas, &layer, left, top, transform, mask);
+        Ok(())
+    }
+
+    /// Draws the emboss highlight and shadow, clipped to the glyph coverage.
+    pub(crate) fn draw_emboss(&self, canvas: &mut PixmapMut<'_>, glyph_cache: &mut GlyphCache, transform: Transform, mask: Option<&Mask>) -> Result<(), Error> {
+        let Some((highlight, shadow, depth)) = self.emboss else { return Ok(()) };
+        let offset = depth.round() as isize;
+        if offset == 0 { return Ok(()) }
+        let Some((glyphs, left, top)) = self.coverage_layer(glyph_cache, ColorU8::from_rgba(0, 0, 0, u8::MAX), offset.unsigned_abs() as f32)? else { return Ok(()) };
+        let (width, height) = (glyphs.width() as isize, glyphs.height() as isize);
+        let coverage = |x: isize, y: isize| if x < 0 || y < 0 || x >= width || y >= height {
+            0.0
+        } else {
+            f32::from(glyphs.pixels()[(y * width + x) as usize].alpha()) / 255.0
+        };
+        let mut layer = Pixmap::new(glyphs.width(), glyphs.height()).ok_or(Error::Canvas { width: glyphs.width(), height: glyphs.height() })?;
+        for (idx, pixel) in layer.pixels_mut().iter_mut().enumerate() {
+            let (x, y) = (idx as isize % width, idx as isize / width);
+            let inside = coverage(x, y);
+            if inside == 0.0 { continue }
+            // an edge is lit if moving towards the light leaves the glyph
+            let lit = inside * (1.0 - coverage(x - offset, y - offset));
+            let shaded = inside * (1.0 - coverage(x + offset, y + offset));
+            let mut color = [0.0; 4];
+            for (color_u8, amount) in [(highlight, lit), (shadow, shaded)] {
+                let alpha = f32::from(color_u8.alpha()) / 255.0 * amount;
+                for (channel, value) in color.iter_mut().zip([color_u8.red(), color_u8.green(), color_u8.blue()]) {
+                    *channel = f32::from(value) / 255.0 * alpha + *channel * (1.0 - alpha);
+                }
+                color[3] = alpha + color[3] * (1.0 - alpha);
+            }
+            let [red, green, blue, alpha] = color.map(|channel| (channel * 255.0).round() as u8);
+            *pixel = PremultipliedColorU8::from_rgba(red.min(alpha), green.min(alpha), blue.min(alpha), alpha).unwrap_or(PremultipliedColorU8::TRANSPARENT);
+        }
+        self.draw_layer(canvas, &layer, left, top, transform, mask);
+        Ok(())
+    }
+}
+
+fn box_blur(pixmap: &mut Pixmap, radius: usize) {
+    let (width, height) = (pixmap.width() as usize, pixmap.height() as usize);
+    let data = pixmap.data_mut();
+    let mut line = Vec::default();
+    for y in 0..height {
+        blur_line(data, y * width * 4, 4, width, radius, &mut line);
+    }
+    for x in 0..width {
+        blur_line(data, x * 4, width * 4, height, radius, &mut line);
+    }
+}
+
+fn blur_line(data: &mut [u8], start: usize, stride: usize, len: usize, radius: usize, line: &mut Vec<[u8; 4]>) {
+    line.clear();
+    line.extend((0..len).map(|idx| {
+        let offset = start + idx * stride;
+        [data[offset], data[offset + 1], data[offset + 2], data[offset + 3]]
+    }));
+    let window = 2 * radius as u32 + 1;
+    let mut sum = [0u32; 4];
+    for pixel in &line[..radius.min(len)] {
+        for (sum, &value) in sum.iter_mut().zip(pixel) { *sum += u32::from(value) }
+    }
+    for idx in 0..len {
+        if let Some(pixel) = line.get(idx + radius) {
+            for (sum, &value) in sum.iter_mut().zip(pixel) { *sum += u32::from(value) }
+        }
+        if let Some(pixel) = idx.checked_sub(radius + 1).map(|idx| line[idx]) {
+            for (sum, value) in sum.iter_mut().zip(pixel) { *sum -= u32::from(value) }
+        }
+        let offset = start + idx * stride;
+        for (channel, &sum) in sum.iter().enumerate() {
+            data[offset + channel] = (sum / window) as u8;
+        }
+    }
+}
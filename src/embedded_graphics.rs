@@ -0,0 +1,46 @@
+use {
+    embedded_graphics_core::{
+        Pixel,
+        draw_target::DrawTarget,
+        geometry::Point,
+    },
+    tiny_skia::ColorU8,
+    crate::{
+        Error,
+        GlyphCache,
+        TextBox,
+    },
+};
+#[cfg(not(any(feature = "std", test)))] use crate::float::Float as _;
+
+#[derive(Debug, thiserror::Error)]
+pub enum DrawTargetError<E> {
+    #[error(transparent)]
+    Text(#[from] Error),
+    #[error("draw target error: {0:?}")]
+    Target(E),
+}
+
+impl TextBox<'_, '_> {
+    pub fn draw_target<D: DrawTarget>(&self, target: &mut D, glyph_cache: &mut GlyphCache, mut map_color: impl FnMut(ColorU8) -> Option<D::Color>) -> Result<(), DrawTargetError<D::Error>> {
+        for (idx, glyph) in self.glyphs.iter().enumerate() {
+            if glyph.width > 0 && glyph.height > 0 {
+                for (key, padding) in self.glyph_layers(idx) {
+                    let glyph_canvas = self.cached_glyph(glyph, key, glyph_cache)?;
+                    let (x, y) = ((glyph.x - padding).round() as i32, (glyph.y - padding).round() as i32);
+                    let pixels = glyph_canvas.pixels().iter().enumerate().filter_map(|(idx, pixel)| {
+                        let color = map_color(pixel.demultiply())?;
+                        let idx = idx as u32;
+                        Some(Pixel(Point::new(x + (idx % glyph_canvas.width()) as i32, y + (idx / glyph_canvas.width()) as i32), color))
+                    });
+                    target.draw_iter(pixels).map_err(DrawTargetError::Target)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn draw_target_threshold<D: DrawTarget>(&self, target: &mut D, glyph_cache: &mut GlyphCache, color: D::Color, threshold: u8) -> Result<(), DrawTargetError<D::Error>> {
+        self.draw_target(target, glyph_cache, |pixel| (pixel.alpha() >= threshold).then_some(color))
+    }
+}
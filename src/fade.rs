@@ -0,0 +1,38 @@
+use {
+    tiny_skia::{
+        Mask,
+        Transform,
+    },
+    crate::{
+        Error,
+        TextBox,
+    },
+};
+#[cfg(not(any(feature = "std", test)))] use crate::float::Float as _;
+
+impl TextBox<'_, '_> {
+    /// A mask that fades the text, moved by the translation `transform`, out towards the right and bottom edges of the bounds where it overflows them, combined with `mask`.
+    pub(crate) fn fade_mask(&self, width: u32, height: u32, transform: Transform, mask: Option<&Mask>) -> Result<Option<Mask>, Error> {
+        let Some(distance) = self.fade_overflow else { return Ok(None) };
+        let distance = distance.max(1.0);
+        let (right, bottom) = (self.inner_bounds.right(), self.inner_bounds.bottom());
+        let (overflows_right, overflows_bottom) = self.glyphs.iter()
+            .filter(|glyph| glyph.width > 0 && glyph.height > 0)
+            .fold((false, false), |(overflows_right, overflows_bottom), glyph| (
+                overflows_right || glyph.x + glyph.width as f32 > right,
+                overflows_bottom || glyph.y + glyph.height as f32 > bottom,
+            ));
+        if !overflows_right && !overflows_bottom { return Ok(None) }
+        let (right, bottom) = (right + transform.tx, bottom + transform.ty);
+        let fade = |position: f32, edge: f32| ((edge - position) / distance).clamp(0.0, 1.0);
+        let mut fade_mask = Mask::new(width, height).ok_or(Error::Canvas { width, height })?;
+        for (idx, value) in fade_mask.data_mut().iter_mut().enumerate() {
+            let (x, y) = ((idx % width as usize) as f32 + 0.5, (idx / width as usize) as f32 + 0.5);
+            let mut alpha = f32::from(mask.map_or(u8::MAX, |mask| mask.data()[idx]));
+            if overflows_right { alpha *= fade(x, right) }
+            if overflows_bottom { alpha *= fade(y, bottom) }
+            *value = alpha.round() as u8;
+        }
+        Ok(Some(fade_mask))
+    }
+}
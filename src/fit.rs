@@ -0,0 +1,68 @@
+use {
+    alloc::vec::Vec,
+    fontdue::layout::Layout,
+    crate::{
+        Builder,
+        HorizontalScale,
+        InnerBounds,
+        Size,
+        TextBox,
+        line_width,
+    },
+};
+
+impl<'f, 't> Builder<'f, 't, InnerBounds> {
+    /// Builds the text box with each line of the text kept on a single line, fitted to the bounds the way headlines are fitted in print:
+    /// the tracking is tightened down to `min_tracking` first, and only if that's not enough, the size is reduced down to `min_size`.
+    ///
+    /// If the text doesn't fit even at `min_tracking` and `min_size`, it overflows the bounds.
+    pub fn build_fit<'l>(self, layout: &'l mut Layout, min_tracking: f32, min_size: f32) -> TextBox<'f, 'l> {
+        if self.scale_factor != 1.0 {
+            let scale = self.scale_factor;
+            return self.scaled().build_fit(layout, min_tracking * scale, min_size * scale)
+        }
+        let text = self.prepared_text();
+        let lines = text.split('\n')
+            .map(|line| {
+                let line = line.trim_end();
+                (line, line.chars().count().saturating_sub(1) as f32)
+            })
+            .collect::<Vec<_>>();
+        let [bounds_width, bounds_height] = [self.bounds.0.width(), self.bounds.0.height()];
+        let scale_x = match self.horizontal_scale {
+            HorizontalScale::Factor(factor) => factor,
+            HorizontalScale::Fill => 1.0,
+        };
+        let natural_width = |size: f32| lines.iter().map(|&(line, _)| line_width(self.font, line, size)).fold(0.0, f32::max);
+        let fits = |size: f32, tracking: f32| {
+            let height = self.font.horizontal_line_metrics(size).map_or(size, |metrics| metrics.new_line_size) * lines.len() as f32;
+            height <= bounds_height && lines.iter().all(|&(line, gaps)| line_width(self.font, line, size) * scale_x + tracking * gaps <= bounds_width)
+        };
+        // the loosest tracking at the current size that fits every line, but never looser than requested
+        let tracking = lines.iter()
+            .filter(|&&(_, gaps)| gaps > 0.0)
+            .map(|&(line, gaps)| (bounds_width - line_width(self.font, line, self.size_px()) * scale_x) / gaps)
+            .fold(self.px(self.tracking), f32::min)
+            .max(min_tracking);
+        let (size, tracking) = if fits(self.size_px(), tracking) || self.size_px() <= min_size {
+            (self.size_px(), tracking)
+        } else if fits(min_size, min_tracking) {
+            let (mut low, mut high) = (min_size, self.size_px());
+            while high - low > 0.01 {
+                let mid = (low + high) / 2.0;
+                if fits(mid, min_tracking) { low = mid } else { high = mid }
+            }
+            (low, min_tracking)
+        } else {
+            (min_size, min_tracking)
+        };
+        let builder = Self { size: Size::Px(size), tracking: Size::Px(tracking), ..self };
+        // wide enough that fontdue doesn't wrap lines which only fit after tracking is applied
+        let layout_width = bounds_width.max(natural_width(size));
+        builder.reset_layout_with_width(layout, layout_width);
+        let mut text_box = builder.into_text_box(layout);
+        text_box.layout_width = layout_width;
+        text_box.append(self.text);
+        text_box
+    }
+}
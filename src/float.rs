@@ -0,0 +1,34 @@
+//! Floating-point functions that are only inherent methods with `std`, implemented using libm for `no_std` builds.
+
+pub(crate) trait Float {
+    fn ceil(self) -> Self;
+    fn floor(self) -> Self;
+    fn fract(self) -> Self;
+    fn hypot(self, other: Self) -> Self;
+    fn powf(self, n: Self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn rem_euclid(self, rhs: Self) -> Self;
+    fn round(self) -> Self;
+    fn sin(self) -> Self;
+    fn sin_cos(self) -> (Self, Self) where Self: Sized;
+    fn sqrt(self) -> Self;
+}
+
+impl Float for f32 {
+    fn ceil(self) -> Self { libm::ceilf(self) }
+    fn floor(self) -> Self { libm::floorf(self) }
+    fn fract(self) -> Self { self - libm::truncf(self) }
+    fn hypot(self, other: Self) -> Self { libm::hypotf(self, other) }
+    fn powf(self, n: Self) -> Self { libm::powf(self, n) }
+    fn powi(self, n: i32) -> Self { libm::powf(self, n as f32) }
+
+    fn rem_euclid(self, rhs: Self) -> Self {
+        let rem = libm::fmodf(self, rhs);
+        if rem < 0.0 { rem + libm::fabsf(rhs) } else { rem }
+    }
+
+    fn round(self) -> Self { libm::roundf(self) }
+    fn sin(self) -> Self { libm::sinf(self) }
+    fn sin_cos(self) -> (Self, Self) { libm::sincosf(self) }
+    fn sqrt(self) -> Self { libm::sqrtf(self) }
+}
@@ -0,0 +1,184 @@
+use {
+    alloc::{
+        string::String,
+        vec,
+        vec::Vec,
+    },
+    fontdue::layout::{
+        CoordinateSystem,
+        GlyphPosition,
+        Layout,
+        LayoutSettings,
+        TextStyle,
+    },
+    itertools::Itertools as _,
+    tiny_skia::{
+        Path,
+        PathSegment,
+        Point,
+    },
+    crate::TextBox,
+};
+#[cfg(not(any(feature = "std", test)))] use crate::float::Float as _;
+
+/// The number of line segments each curve of a shape is approximated with.
+const CURVE_STEPS: usize = 16;
+
+/// The outline of a path as closed polygons.
+fn flatten(path: &Path) -> Vec<Vec<Point>> {
+    let mut polygons = Vec::<Vec<Point>>::default();
+    let mut current = Point::zero();
+    for segment in path.segments() {
+        match segment {
+            PathSegment::MoveTo(point) => {
+                polygons.push(vec![point]);
+                current = point;
+                continue
+            }
+            PathSegment::LineTo(point) => current = point,
+            PathSegment::QuadTo(control, point) => {
+                let start = current;
+                for step in 1..=CURVE_STEPS {
+                    let t = step as f32 / CURVE_STEPS as f32;
+                    let u = 1.0 - t;
+                    let x = u * u * start.x + 2.0 * u * t * control.x + t * t * point.x;
+                    let y = u * u * start.y + 2.0 * u * t * control.y + t * t * point.y;
+                    if let Some(polygon) = polygons.last_mut() { polygon.push(Point::from_xy(x, y)) }
+                }
+                current = point;
+                continue
+            }
+            PathSegment::CubicTo(control1, control2, point) => {
+                let start = current;
+                for step in 1..=CURVE_STEPS {
+                    let t = step as f32 / CURVE_STEPS as f32;
+                    let u = 1.0 - t;
+                    let x = u * u * u * start.x + 3.0 * u * u * t * control1.x + 3.0 * u * t * t * control2.x + t * t * t * point.x;
+                    let y = u * u * u * start.y + 3.0 * u * u * t * control1.y + 3.0 * u * t * t * control2.y + t * t * t * point.y;
+                    if let Some(polygon) = polygons.last_mut() { polygon.push(Point::from_xy(x, y)) }
+                }
+                current = point;
+                continue
+            }
+            PathSegment::Close => continue,
+        }
+        if let Some(polygon) = polygons.last_mut() { polygon.push(current) }
+    }
+    polygons
+}
+
+/// The horizontal intervals inside the polygons along a horizontal line, using the even-odd rule.
+fn scanline(polygons: &[Vec<Point>], y: f32) -> Vec<(f32, f32)> {
+    let mut crossings = polygons.iter()
+        .flat_map(|polygon| polygon.iter().zip(polygon.iter().cycle().skip(1)))
+        .filter(|(start, end)| (start.y <= y) != (end.y <= y))
+        .map(|(start, end)| start.x + (y - start.y) / (end.y - start.y) * (end.x - start.x))
+        .collect::<Vec<_>>();
+    crossings.sort_by(f32::total_cmp);
+    crossings.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect()
+}
+
+/// The intervals contained in both lists of intervals.
+fn intersect(a: &[(f32, f32)], b: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    a.iter()
+        .cartesian_product(b)
+        .map(|(&(left1, right1), &(left2, right2))| (left1.max(left2), right1.min(right2)))
+        .filter(|(left, right)| right > left)
+        .sorted_by(|(left1, _), (left2, _)| left1.total_cmp(left2))
+        .collect()
+}
+
+impl TextBox<'_, '_> {
+    /// Whether lines are flowed into per-line segments rather than laid out across the full bounds width.
+    pub(crate) fn flows(&self) -> bool {
+        self.shape.is_some() || !self.exclusions.is_empty()
+    }
+
+    /// The horizontal segments of the bounds available to text between the given vertical coordinates, from left to right.
+    fn flow_segments(&self, top: f32, bottom: f32) -> Vec<(f32, f32)> {
+        let mut segments = vec![(self.inner_bounds.left(), self.inner_bounds.right())];
+        if let Some(ref shape) = self.shape {
+            let polygons = flatten(shape);
+            // the outline is piecewise linear, so the narrowest parts of the band are at its edges or at vertices within it
+            let ys = [top, bottom].into_iter()
+                .chain(polygons.iter().flatten().map(|point| point.y).filter(|&y| top < y && y < bottom))
+                .map(|y| y.clamp(top + 0.01, bottom - 0.01));
+            for y in ys {
+                segments = intersect(&segments, &scanline(&polygons, y));
+            }
+        }
+        for exclusion in &self.exclusions {
+            if exclusion.top() >= bottom || exclusion.bottom() <= top { continue }
+            segments = segments.into_iter().flat_map(|(left, right)| [
+                (left, right.min(exclusion.left())),
+                (left.max(exclusion.right()), right),
+            ]).filter(|(left, right)| right > left).collect();
+        }
+        segments
+    }
+
+    /// The glyphs that can end up on a line of the given width: up to the first hard line break, or until their advances overflow the width.
+    ///
+    /// Laying out just these gives fontdue the same first line as the whole rest of the text, without re-laying out all of it for every line.
+    fn flow_window<'g>(&self, glyphs: &'g [GlyphPosition], width: f32) -> &'g [GlyphPosition] {
+        let mut advance = 0.0;
+        let end = glyphs.iter().position(|glyph| {
+            // fontdue uses rounded up advances and doesn't advance past control characters
+            if !glyph.char_data.is_control() {
+                advance += self.font.metrics_indexed(glyph.key.glyph_index, glyph.key.px).advance_width.ceil();
+            }
+            glyph.parent == '\n' || advance > width
+        });
+        &glyphs[..end.map_or(glyphs.len(), |idx| idx + 1)]
+    }
+
+    /// Breaks the laid out text into lines again, one per available segment, from the top of the bounds.
+    ///
+    /// Each line is laid out by fontdue on its own, so glyphs keep their sizes and byte offsets from the original layout.
+    pub(crate) fn flow(&mut self) {
+        let source = self.layout.glyphs().clone();
+        let line_height = self.font.horizontal_line_metrics(self.size).map_or(self.size, |metrics| metrics.new_line_size).ceil();
+        let mut scratch = Layout::new(CoordinateSystem::PositiveYDown);
+        self.glyphs.clear();
+        self.lines.clear();
+        let mut pos = 0;
+        let mut top = self.inner_bounds.top();
+        while pos < source.len() {
+            // past the bottom of the bounds, overflowing text is laid out across the full width so it doesn't flow forever
+            let overflowing = top >= self.inner_bounds.bottom();
+            let mut segments = self.flow_segments(top, top + line_height);
+            if overflowing && segments.is_empty() {
+                segments.push((self.inner_bounds.left(), self.inner_bounds.right()));
+            }
+            for (left, right) in segments {
+                if pos >= source.len() { break }
+                let first = &source[pos];
+                if !overflowing && self.font.metrics_indexed(first.key.glyph_index, first.key.px).advance_width.ceil() > right - left { continue }
+                scratch.reset(&LayoutSettings {
+                    x: left,
+                    y: top,
+                    max_width: Some(right - left),
+                    horizontal_align: self.halign,
+                    ..LayoutSettings::default()
+                });
+                for (px, glyphs) in &self.flow_window(&source[pos..], right - left).iter().chunk_by(|glyph| glyph.key.px) {
+                    scratch.append(core::slice::from_ref(self.font), &TextStyle::new(&glyphs.map(|glyph| glyph.parent).collect::<String>(), px, 0));
+                }
+                let Some(mut line) = scratch.lines().and_then(|lines| lines.first()).copied() else { break };
+                let glyph_start = self.glyphs.len();
+                self.glyphs.extend(scratch.glyphs()[..=line.glyph_end].iter().zip(&source[pos..]).map(|(glyph, original)| GlyphPosition {
+                    byte_offset: original.byte_offset,
+                    ..*glyph
+                }));
+                pos += line.glyph_end + 1;
+                line.glyph_start = glyph_start;
+                line.glyph_end = self.glyphs.len() - 1;
+                self.lines.push(line);
+                // a hard line break continues below rather than in the next segment
+                if self.glyphs.last().is_some_and(|glyph| glyph.parent == '\n') { break }
+            }
+            top += line_height;
+        }
+        self.height = top - self.inner_bounds.top();
+    }
+}
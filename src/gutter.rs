@@ -0,0 +1,114 @@
+use {
+    alloc::{
+        string::{
+            String,
+            ToString as _,
+        },
+        vec::Vec,
+    },
+    fontdue::{
+        Font,
+        layout::{
+            CoordinateSystem,
+            HorizontalAlign,
+            Layout,
+        },
+    },
+    tiny_skia::{
+        ColorU8,
+        PixmapMut,
+        Rect,
+        Transform,
+    },
+    crate::{
+        Builder,
+        Error,
+        GlyphCache,
+        TextBox,
+        VerticalAlign,
+        line_width,
+        union,
+    },
+};
+#[cfg(not(any(feature = "std", test)))] use crate::float::Float as _;
+
+/// A column of line numbers drawn to the left of a text box by `TextBox::draw_gutter`, e.g. for rendering code.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gutter {
+    pub color: ColorU8,
+    pub background: Option<ColorU8>,
+    /// The width of the gutter, which ends at the left edge of the text box's inner bounds.
+    pub width: f32,
+    /// Space between the line numbers and the text.
+    pub padding: f32,
+    /// The number of the first line of the text.
+    pub first_number: usize,
+}
+
+impl Default for Gutter {
+    fn default() -> Self {
+        Self {
+            color: ColorU8::from_rgba(0x80, 0x80, 0x80, u8::MAX),
+            background: None,
+            width: 48.0,
+            padding: 12.0,
+            first_number: 1,
+        }
+    }
+}
+
+impl Gutter {
+    /// The width needed to fit the line numbers of a text with the given number of lines, including padding on both sides.
+    pub fn width_for(&self, font: &Font, size: f32, lines: usize) -> f32 {
+        let last_number = self.first_number + lines.saturating_sub(1);
+        (line_width(font, &"0".repeat(last_number.to_string().len()), size) + 2.0 * self.padding).ceil()
+    }
+}
+
+impl TextBox<'_, '_> {
+    /// Draws right-aligned line numbers in a gutter to the left of the text box. Only the first of the lines a line of text is wrapped into is numbered.
+    ///
+    /// This only draws the gutter, so the text itself still needs to be drawn.
+    pub fn draw_gutter(&self, mut canvas: PixmapMut<'_>, glyph_cache: &mut GlyphCache, gutter: &Gutter) -> Result<Option<Rect>, Error> {
+        let bottom = self.lines.last().map_or(self.inner_bounds.bottom(), |line| self.inner_bounds.bottom().max(line.baseline_y + self.y_offset - line.min_descent));
+        let (x, y, width, height) = (self.inner_bounds.left() - gutter.width, self.inner_bounds.top(), gutter.width, bottom - self.inner_bounds.top());
+        let rect = Rect::from_xywh(x, y, width, height).ok_or(Error::Rect { x, y, width, height })?;
+        let mut dirty = None;
+        if let Some(background) = gutter.background {
+            canvas.fill_rect(rect, &self.paint(background), Transform::identity(), None);
+            dirty = Some(rect);
+        }
+        let Some(first_line) = self.lines.first() else { return Ok(dirty) };
+        // one line of numbers per line of the text box, left empty where a line of text wraps
+        let mut number = gutter.first_number;
+        let mut numbers = Vec::with_capacity(self.lines.len());
+        let mut starts_line = true;
+        let mut prev_baseline = None;
+        for line in &self.lines {
+            // lines flowed into multiple segments share a baseline
+            if prev_baseline != Some(line.baseline_y) {
+                if starts_line {
+                    numbers.push(number.to_string());
+                    number += 1;
+                } else {
+                    numbers.push(String::default());
+                }
+            }
+            prev_baseline = Some(line.baseline_y);
+            starts_line = self.glyphs.get(line.glyph_end).is_some_and(|glyph| glyph.parent == '\n');
+        }
+        let numbers = numbers.join("\n");
+        let bounds = Rect::from_xywh(x, y, (width - gutter.padding).max(1.0), height).ok_or(Error::Rect { x, y, width, height })?;
+        let mut layout = Layout::new(CoordinateSystem::PositiveYDown);
+        let text_box = Builder::new(self.font, &numbers)
+            .size(self.size)
+            .color(gutter.color)
+            .opacity(self.opacity)
+            .hinting(self.hinting)
+            .halign(HorizontalAlign::Right)
+            .valign(VerticalAlign::Baseline(first_line.baseline_y + self.y_offset - y))
+            .bounds_inner(bounds)
+            .build(&mut layout);
+        Ok(union(dirty, text_box.draw(canvas, glyph_cache)?))
+    }
+}
@@ -0,0 +1,73 @@
+use {
+    image::RgbaImage,
+    tiny_skia::{
+        ColorU8,
+        IntRect,
+        Pixmap,
+        PixmapRef,
+        Rect,
+    },
+    crate::{
+        Error,
+        GlyphCache,
+        TextBox,
+    },
+};
+
+pub fn pixmap_from_image(image: &RgbaImage) -> Result<Pixmap, Error> {
+    let mut pixmap = Pixmap::new(image.width(), image.height()).ok_or(Error::Canvas { width: image.width(), height: image.height() })?;
+    for (&image::Rgba([red, green, blue, alpha]), pixel) in image.pixels().zip(pixmap.pixels_mut()) {
+        *pixel = ColorU8::from_rgba(red, green, blue, alpha).premultiply();
+    }
+    Ok(pixmap)
+}
+
+pub fn image_from_pixmap(pixmap: PixmapRef<'_>) -> RgbaImage {
+    let mut image = RgbaImage::new(pixmap.width(), pixmap.height());
+    for (pixel, image_pixel) in pixmap.pixels().iter().zip(image.pixels_mut()) {
+        let color = pixel.demultiply();
+        *image_pixel = image::Rgba([color.red(), color.green(), color.blue(), color.alpha()]);
+    }
+    image
+}
+
+/// Copies the pixels of `pixmap` inside `rect` into `image`. Pixels outside `rect` are left untouched, so they don't lose precision by being premultiplied and demultiplied.
+fn write_back(pixmap: PixmapRef<'_>, image: &mut RgbaImage, rect: IntRect) {
+    for y in rect.top()..rect.bottom() {
+        for x in rect.left()..rect.right() {
+            let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) else { continue };
+            let Some(pixel) = pixmap.pixel(x, y) else { continue };
+            let color = pixel.demultiply();
+            image.put_pixel(x, y, image::Rgba([color.red(), color.green(), color.blue(), color.alpha()]));
+        }
+    }
+}
+
+impl TextBox<'_, '_> {
+    pub fn draw_image(&self, image: &mut RgbaImage, glyph_cache: &mut GlyphCache) -> Result<Option<Rect>, Error> {
+        let mut pixmap = pixmap_from_image(image)?;
+        let dirty = self.draw(pixmap.as_mut(), glyph_cache)?;
+        if let Some(rect) = dirty.and_then(|dirty| dirty.round_out()) {
+            write_back(pixmap.as_ref(), image, rect);
+        }
+        Ok(dirty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keep_pixels_outside_dirty_rect() {
+        let semi_transparent = image::Rgba([201, 99, 7, 3]);
+        let mut image = RgbaImage::from_pixel(4, 4, semi_transparent);
+        let mut pixmap = pixmap_from_image(&image).unwrap();
+        pixmap.fill(tiny_skia::Color::BLACK);
+        write_back(pixmap.as_ref(), &mut image, IntRect::from_xywh(0, 0, 2, 4).unwrap());
+        assert_eq!(*image.get_pixel(1, 1), image::Rgba([0, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(2, 1), semi_transparent);
+        // a full round trip would have lost precision
+        assert_ne!(image_from_pixmap(pixmap_from_image(&RgbaImage::from_pixel(1, 1, semi_transparent)).unwrap().as_ref()).get_pixel(0, 0), &semi_transparent);
+    }
+}
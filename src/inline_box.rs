@@ -0,0 +1,50 @@
+use {
+    alloc::{
+        string::{
+            String,
+            ToString as _,
+        },
+        vec,
+        vec::Vec,
+    },
+    tiny_skia::Rect,
+    crate::TextBox,
+};
+#[cfg(not(any(feature = "std", test)))] use crate::float::Float as _;
+
+/// Marks the position of an inline box in the text.
+pub(crate) const OBJECT_REPLACEMENT: char = '\u{fffc}';
+
+impl TextBox<'_, '_> {
+    /// The text runs and font sizes used to reserve horizontal space for an inline box of the given width.
+    ///
+    /// fontdue can't lay out glyphs with custom advances, so the box is represented by no-break spaces,
+    /// set at sizes no larger than the text's so the box doesn't make its line taller.
+    /// fontdue rounds each advance up to whole pixels, so the spaces come in two sizes whose advances differ by one pixel.
+    pub(crate) fn inline_box_runs(&self, width: f32) -> Vec<(String, f32)> {
+        let c = if self.font.has_glyph('\u{a0}') { '\u{a0}' } else { ' ' };
+        let advance = self.font.metrics(c, self.size).advance_width;
+        if advance <= 0.0 { return vec![(c.to_string(), self.size)] }
+        let width = width.ceil().max(1.0) as usize;
+        let count = width.div_ceil(advance.ceil() as usize);
+        let (glyph_advance, wider) = (width / count, width % count);
+        // never larger than the text, which would make the line taller
+        let px = |glyph_advance: usize| (self.size * (glyph_advance as f32 - 0.01) / advance).min(self.size);
+        [(wider, glyph_advance + 1), (count - wider, glyph_advance)].into_iter()
+            .filter(|&(count, _)| count > 0)
+            .map(|(count, glyph_advance)| (c.to_string().repeat(count), px(glyph_advance)))
+            .collect()
+    }
+
+    /// The rectangles of the inline boxes added using [`Builder::inline_box`](crate::Builder::inline_box), in the order they appear in the text.
+    ///
+    /// Each box sits on the baseline of the line it ends up on. Boxes are not drawn; position widgets at these rectangles instead.
+    pub fn inline_box_rects(&self) -> Vec<Rect> {
+        self.inline_box_glyphs.iter().zip(&self.inline_boxes).filter_map(|(glyphs, &[width, height])| {
+            let line = self.lines.iter().find(|line| (line.glyph_start..=line.glyph_end).contains(&glyphs.start))?;
+            let (left, _) = self.glyph_extent(self.glyphs.get(glyphs.start)?);
+            let baseline = line.baseline_y + self.y_offset;
+            Rect::from_xywh(left, baseline - height, width * self.scale_x, height)
+        }).collect()
+    }
+}
@@ -1,37 +1,479 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 use {
-    std::collections::hash_map::{
-        self,
-        HashMap,
+    alloc::{
+        borrow::{
+            Cow,
+            ToOwned as _,
+        },
+        collections::BTreeSet,
+        format,
+        string::String,
+        sync::Arc,
+        vec,
+        vec::Vec,
+    },
+    core::{
+        fmt,
+        hash::Hash,
+        ops::Range,
     },
     fontdue::{
         Font,
         layout::{
+            GlyphPosition,
             GlyphRasterConfig,
             HorizontalAlign,
             Layout,
             LayoutSettings,
+            LinePosition,
             TextStyle,
-            VerticalAlign,
+        },
+    },
+    hashbrown::{
+        HashSet,
+        hash_map::{
+            self,
+            HashMap,
         },
     },
     itertools::Itertools as _,
-    noisy_float::prelude::*,
     tiny_skia::*,
+    unicode_normalization::UnicodeNormalization as _,
+    crate::{
+        inline_box::OBJECT_REPLACEMENT,
+        measure::{
+            Run,
+            RunKey,
+        },
+    },
+};
+#[cfg(not(any(feature = "std", test)))] use crate::float::Float as _;
+pub use crate::accessibility::{
+    AccessibleLine,
+    AccessibleText,
+};
+pub use crate::badge::Badge;
+pub use crate::bmfont::{
+    BmFont,
+    export_bmfont,
+    export_bmfont_msdf,
+};
+pub use crate::bubble::Bubble;
+pub use crate::captcha::Distortion;
+pub use crate::color::{
+    Hsl,
+    Hsv,
+    Oklch,
+};
+pub use crate::damage::DamageTracker;
+pub use crate::decoration::{
+    Decoration,
+    DecorationLine,
+    LineStyle,
+};
+#[cfg(feature = "editor")] pub use crate::editor::Editor;
+#[cfg(feature = "embedded-graphics")] pub use crate::embedded_graphics::DrawTargetError;
+pub use crate::style::{
+    DecorationStyle,
+    Direction,
+    EmbossStyle,
+    GlowStyle,
+    HAlign,
+    StripesStyle,
+    StrokeStyle,
+    Style,
+    Theme,
+};
+pub use crate::gutter::Gutter;
+pub use crate::nine_slice::NineSlice;
+pub use crate::options::{
+    DynBounds,
+    TextOptions,
+    render,
+};
+pub use crate::owned::OwnedTextBox;
+#[cfg(feature = "std")] pub use crate::pool::{
+    LayoutPool,
+    PooledLayout,
 };
+pub use crate::snapshot::TextBoxSnapshot;
+pub use crate::stripes::StripeWidth;
+pub use crate::subtitle::SafeArea;
+pub use crate::truncate::Truncation;
+pub use crate::units::Size;
+pub use crate::words::TextSpan;
+pub use crate::sdf::{
+    msdf_glyph,
+    sdf_glyph,
+};
+#[cfg(feature = "image")] pub use crate::image_interop::{
+    image_from_pixmap,
+    pixmap_from_image,
+};
+#[cfg(feature = "wgpu")] pub use crate::wgpu_interop::{
+    AtlasUpdate,
+    GpuAtlas,
+    GpuText,
+    Vertex,
+};
+
+#[cfg(feature = "cli")] use {
+    clap as _,
+    png as _,
+};
+#[cfg(all(any(feature = "std", test), feature = "libm"))] use libm as _;
+
+mod accessibility;
+mod badge;
+mod bmfont;
+mod bubble;
+mod captcha;
+mod color;
+mod damage;
+mod decoration;
+mod effects;
+#[cfg(feature = "editor")] mod editor;
+mod fade;
+mod fit;
+#[cfg(not(any(feature = "std", test)))] mod float;
+mod flow;
+mod gutter;
+mod inline_box;
+#[cfg(feature = "embedded-graphics")] mod embedded_graphics;
+mod nine_slice;
+mod options;
+mod owned;
+mod measure;
+#[cfg(feature = "std")] mod persist;
+#[cfg(feature = "std")] mod pool;
+mod redraw;
+mod regions;
+mod sdf;
+mod snapshot;
+mod stripes;
+mod style;
+mod subtitle;
+mod tiles;
+mod truncate;
+mod units;
+mod watermark;
+mod words;
+#[cfg(feature = "image")] mod image_interop;
+#[cfg(feature = "syntect")] mod syntect_interop;
+#[cfg(feature = "wgpu")] mod wgpu_interop;
+
+#[cfg(not(any(feature = "std", feature = "libm")))] compile_error!("the `libm` feature is required when building without `std`");
 
 pub const DEFAULT_SIZE: f32 = 24.0;
+/// The resolution at which a pixel is 1/96 inch, as in CSS.
+pub const DEFAULT_DPI: f32 = 96.0;
 
 pub trait Bounds {}
 
+#[derive(Debug, Clone, Copy)]
 pub struct DefaultBounds;
 impl Bounds for DefaultBounds {}
 
+#[derive(Debug, Clone, Copy)]
 pub struct InnerBounds(Rect);
 impl Bounds for InnerBounds {}
 
+#[derive(Debug, Clone, Copy)]
 pub struct OuterBounds(Rect);
 impl Bounds for OuterBounds {}
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum VerticalAlign {
+    Top,
+    Middle,
+    Bottom,
+    Baseline(f32),
+    MiddleCapHeight,
+    MiddleXHeight,
+}
+
+impl From<fontdue::layout::VerticalAlign> for VerticalAlign {
+    fn from(valign: fontdue::layout::VerticalAlign) -> Self {
+        match valign {
+            fontdue::layout::VerticalAlign::Top => Self::Top,
+            fontdue::layout::VerticalAlign::Middle => Self::Middle,
+            fontdue::layout::VerticalAlign::Bottom => Self::Bottom,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Metrics {
+    pub ascent: f32,
+    pub descent: f32,
+    pub line_gap: f32,
+    pub x_height: f32,
+    pub cap_height: f32,
+}
+
+impl Metrics {
+    pub fn new(font: &Font, size: f32) -> Option<Self> {
+        let line_metrics = font.horizontal_line_metrics(size)?;
+        Some(Self {
+            ascent: line_metrics.ascent,
+            descent: line_metrics.descent,
+            line_gap: line_metrics.line_gap,
+            x_height: font.metrics('x', size).bounds.height,
+            cap_height: font.metrics('H', size).bounds.height,
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum Hinting {
+    #[default]
+    None,
+    Snap,
+    Full,
+}
+
+/// How the text box aligns its reported rects and glyph positions to whole device pixels.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum PixelRounding {
+    /// Keeps fractional coordinates.
+    #[default]
+    None,
+    /// Rounds glyph positions and the edges of `TextBox::rect_inner` and `TextBox::rect_outer` to the nearest pixel.
+    Nearest,
+    /// Rounds glyph positions to the nearest pixel and the edges of the rects outward, so borders drawn around them never cut into the text.
+    Outward,
+}
+
+impl PixelRounding {
+    fn rect(self, rect: Rect) -> Option<Rect> {
+        match self {
+            Self::None => Some(rect),
+            Self::Nearest => Rect::from_ltrb(rect.left().round(), rect.top().round(), rect.right().round(), rect.bottom().round()),
+            Self::Outward => Rect::from_ltrb(rect.left().floor(), rect.top().floor(), rect.right().ceil(), rect.bottom().ceil()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum Antialiasing {
+    None,
+    #[default]
+    Grayscale,
+    Supersampled,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum MissingGlyphs {
+    #[default]
+    Tofu,
+    Skip,
+    Replace,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum ControlChars {
+    #[default]
+    Keep,
+    Strip,
+    Escape,
+    Error,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum PatternFit {
+    #[default]
+    Tile,
+    Stretch,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum HorizontalScale {
+    Factor(f32),
+    /// Scales the glyphs so the widest line exactly fills the bounds width.
+    Fill,
+}
+
+impl Default for HorizontalScale {
+    fn default() -> Self {
+        Self::Factor(1.0)
+    }
+}
+
+/// A synthesized font width, for fonts without condensed or expanded faces. Named widths match CSS `font-stretch`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum FontStretch {
+    UltraCondensed,
+    ExtraCondensed,
+    Condensed,
+    SemiCondensed,
+    #[default]
+    Normal,
+    SemiExpanded,
+    Expanded,
+    ExtraExpanded,
+    UltraExpanded,
+    /// A width in percent of the normal width, clamped to 50–200%.
+    Percentage(f32),
+}
+
+impl FontStretch {
+    pub fn percentage(&self) -> f32 {
+        match *self {
+            Self::UltraCondensed => 50.0,
+            Self::ExtraCondensed => 62.5,
+            Self::Condensed => 75.0,
+            Self::SemiCondensed => 87.5,
+            Self::Normal => 100.0,
+            Self::SemiExpanded => 112.5,
+            Self::Expanded => 125.0,
+            Self::ExtraExpanded => 150.0,
+            Self::UltraExpanded => 200.0,
+            Self::Percentage(percentage) => percentage.clamp(50.0, 200.0),
+        }
+    }
+}
+
+impl From<FontStretch> for HorizontalScale {
+    fn from(stretch: FontStretch) -> Self {
+        Self::Factor(stretch.percentage() / 100.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GlyphKey {
+    config: GlyphRasterConfig,
+    color: [u8; 4],
+    hinting: Hinting,
+    antialiasing: Antialiasing,
+    stroke: u32,
+    scale_x: u32,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub bytes: usize,
+}
+
+/// The memory taken up by an entry of one of the maps of a [`GlyphCache`]: the pixel data plus the size of the key and value themselves.
+fn entry_bytes<K, V>(pixmap: Option<&Pixmap>) -> usize {
+    size_of::<(K, V)>() + pixmap.map_or(0, |pixmap| pixmap.data().len())
+}
+
+#[derive(Default)]
+pub struct GlyphCache {
+    glyphs: HashMap<GlyphKey, Pixmap>,
+    distance_fields: sdf::DistanceFieldCache,
+    hits: u64,
+    misses: u64,
+    bytes: usize,
+}
+
+impl GlyphCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a rasterized glyph. Misses are counted by the callers on the draw path, so warming the cache doesn't count as missing it.
+    fn insert(&mut self, key: GlyphKey, glyph: Pixmap) -> &mut Pixmap {
+        self.bytes += entry_bytes::<GlyphKey, Pixmap>(Some(&glyph));
+        match self.glyphs.entry(key) {
+            hash_map::Entry::Occupied(mut entry) => {
+                let old = entry.insert(glyph);
+                self.bytes -= entry_bytes::<GlyphKey, Pixmap>(Some(&old));
+                entry.into_mut()
+            }
+            hash_map::Entry::Vacant(entry) => entry.insert(glyph),
+        }
+    }
+
+    pub fn warm(&mut self, fonts: &[Font], sizes: &[f32], charset: &str, colors: &[ColorU8]) -> Result<(), Error> {
+        self.warm_with(fonts, sizes, charset, colors, Hinting::default(), Antialiasing::default())
+    }
+
+    pub fn warm_with(&mut self, fonts: &[Font], sizes: &[f32], charset: &str, colors: &[ColorU8], hinting: Hinting, antialiasing: Antialiasing) -> Result<(), Error> {
+        #[cfg(feature = "rayon")] use rayon::prelude::*;
+
+        let mut seen = HashSet::new();
+        let missing = fonts.iter().enumerate()
+            .cartesian_product(sizes)
+            .cartesian_product(unique(charset.chars()))
+            .cartesian_product(colors)
+            .filter_map(|((((font_index, font), &px), c), color)| {
+                let config = GlyphRasterConfig {
+                    glyph_index: font.lookup_glyph_index(c),
+                    font_hash: font.file_hash(),
+                    px,
+                };
+                let metrics = font.metrics_indexed(config.glyph_index, px);
+                let key = GlyphKey {
+                    color: [color.red(), color.green(), color.blue(), color.alpha()],
+                    stroke: 0,
+                    scale_x: 1.0f32.to_bits(),
+                    config, hinting, antialiasing,
+                };
+                (metrics.width > 0 && metrics.height > 0 && !self.glyphs.contains_key(&key)).then_some((font, font_index, [metrics.width, metrics.height], key))
+            })
+            .filter(|&(_, _, _, key)| seen.insert(key))
+            .collect_vec();
+        #[cfg(feature = "rayon")] let missing = missing.into_par_iter();
+        #[cfg(not(feature = "rayon"))] let missing = missing.into_iter();
+        let rasterized = missing
+            .map(|(font, font_index, size, key)| Ok((key, rasterize(font, font_index, size, key)?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        for (key, glyph) in rasterized {
+            self.insert(key, glyph);
+        }
+        Ok(())
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.glyphs.len() + self.distance_fields.len(),
+            bytes: self.bytes,
+        }
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.glyphs.len() + self.distance_fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty() && self.distance_fields.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.glyphs.clear();
+        self.distance_fields.clear();
+        self.bytes = 0;
+    }
+}
+
+pub fn font_from_bytes(data: &[u8]) -> Result<Font, Error> {
+    Font::from_bytes(data, fontdue::FontSettings::default()).map_err(Error::Font)
+}
+
 pub trait IntoColor {
     fn into_color_u8(self) -> ColorU8;
 }
@@ -46,15 +488,141 @@ impl IntoColor for ColorU8 {
     fn into_color_u8(self) -> ColorU8 { self }
 }
 
+impl IntoColor for [u8; 4] {
+    fn into_color_u8(self) -> ColorU8 {
+        let [red, green, blue, alpha] = self;
+        ColorU8::from_rgba(red, green, blue, alpha)
+    }
+}
+
+impl IntoColor for [u8; 3] {
+    fn into_color_u8(self) -> ColorU8 {
+        let [red, green, blue] = self;
+        ColorU8::from_rgba(red, green, blue, u8::MAX)
+    }
+}
+
+/// Interpreted as `0xRRGGBBAA`.
+impl IntoColor for u32 {
+    fn into_color_u8(self) -> ColorU8 {
+        self.to_be_bytes().into_color_u8()
+    }
+}
+
+pub trait TryIntoColor {
+    fn try_into_color_u8(self) -> Result<ColorU8, Error>;
+}
+
+impl<T: IntoColor> TryIntoColor for T {
+    fn try_into_color_u8(self) -> Result<ColorU8, Error> {
+        Ok(self.into_color_u8())
+    }
+}
+
+/// Parses `#rgb`, `#rgba`, `#rrggbb`, or `#rrggbbaa`. The leading `#` is optional.
+impl TryIntoColor for &str {
+    fn try_into_color_u8(self) -> Result<ColorU8, Error> {
+        let hex = self.strip_prefix('#').unwrap_or(self);
+        let digits = hex.chars().map(|c| c.to_digit(16).map(|digit| digit as u8)).collect::<Option<Vec<_>>>().ok_or_else(|| Error::Color(self.to_owned()))?;
+        let mut channels = match digits.len() {
+            3 | 4 => digits.iter().map(|&digit| digit * 0x11).collect_vec(),
+            6 | 8 => digits.chunks_exact(2).map(|pair| pair[0] << 4 | pair[1]).collect_vec(),
+            _ => return Err(Error::Color(self.to_owned())),
+        };
+        channels.resize(4, u8::MAX);
+        Ok(ColorU8::from_rgba(channels[0], channels[1], channels[2], channels[3]))
+    }
+}
+
 #[must_use]
+#[derive(Clone)]
 pub struct Builder<'f, 't, B: Bounds> {
     font: &'f Font,
     text: &'t str,
     bounds: B,
     color: ColorU8,
-    size: f32,
-    halign: HorizontalAlign,
+    size: Size,
+    halign: HAlign,
     valign: VerticalAlign,
+    hanging_punctuation: bool,
+    outlines: Option<(&'f [u8], f32)>,
+    hinting: Hinting,
+    antialiasing: Antialiasing,
+    opacity: f32,
+    blend_mode: BlendMode,
+    missing_glyphs: MissingGlyphs,
+    on_missing_glyph: Option<&'f dyn Fn(char)>,
+    control_chars: ControlChars,
+    nfc: bool,
+    /// The stroke color is resolved when the text box is built, with `None` standing for the text color.
+    stroke: Option<(Option<ColorU8>, Size)>,
+    decorations: Vec<Decoration>,
+    glow: Option<(ColorU8, f32)>,
+    emboss: Option<(ColorU8, ColorU8, f32)>,
+    pattern: Option<(&'f Pixmap, PatternFit)>,
+    regions: Vec<(Range<usize>, String)>,
+    background: Option<(NineSlice<'f>, Size)>,
+    tracking: Size,
+    horizontal_scale: HorizontalScale,
+    truncation: Option<Truncation>,
+    fade_overflow: Option<f32>,
+    decoration_spans: Vec<(Range<usize>, Decoration)>,
+    direction: Direction,
+    inline_boxes: Vec<[f32; 2]>,
+    exclusions: Vec<Rect>,
+    shape: Option<Path>,
+    stripes: Option<([ColorU8; 2], StripeWidth)>,
+    color_spans: Vec<(Range<usize>, ColorU8)>,
+    scale_factor: f32,
+    dpi: f32,
+    root_size: f32,
+    pixel_rounding: PixelRounding,
+}
+
+impl<B: Bounds + fmt::Debug> fmt::Debug for Builder<'_, '_, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Builder")
+            .field("font", &format_args!("Font {{ file_hash: {} }}", self.font.file_hash()))
+            .field("text", &self.text)
+            .field("bounds", &self.bounds)
+            .field("color", &self.color)
+            .field("size", &self.size)
+            .field("halign", &self.halign)
+            .field("valign", &self.valign)
+            .field("hanging_punctuation", &self.hanging_punctuation)
+            .field("outlines", &self.outlines.map(|(_, threshold)| threshold))
+            .field("hinting", &self.hinting)
+            .field("antialiasing", &self.antialiasing)
+            .field("opacity", &self.opacity)
+            .field("blend_mode", &self.blend_mode)
+            .field("missing_glyphs", &self.missing_glyphs)
+            .field("on_missing_glyph", &self.on_missing_glyph.is_some())
+            .field("control_chars", &self.control_chars)
+            .field("nfc", &self.nfc)
+            .field("stroke", &self.stroke)
+            .field("decorations", &self.decorations)
+            .field("glow", &self.glow)
+            .field("emboss", &self.emboss)
+            .field("pattern", &self.pattern)
+            .field("regions", &self.regions)
+            .field("background", &self.background)
+            .field("tracking", &self.tracking)
+            .field("horizontal_scale", &self.horizontal_scale)
+            .field("truncation", &self.truncation)
+            .field("fade_overflow", &self.fade_overflow)
+            .field("decoration_spans", &self.decoration_spans)
+            .field("direction", &self.direction)
+            .field("inline_boxes", &self.inline_boxes)
+            .field("exclusions", &self.exclusions)
+            .field("shape", &self.shape)
+            .field("stripes", &self.stripes)
+            .field("color_spans", &self.color_spans)
+            .field("scale_factor", &self.scale_factor)
+            .field("dpi", &self.dpi)
+            .field("root_size", &self.root_size)
+            .field("pixel_rounding", &self.pixel_rounding)
+            .finish()
+    }
 }
 
 impl<'f, 't> Builder<'f, 't, DefaultBounds> {
@@ -62,26 +630,64 @@ impl<'f, 't> Builder<'f, 't, DefaultBounds> {
         Self {
             bounds: DefaultBounds,
             color: Color::WHITE.to_color_u8(),
-            size: DEFAULT_SIZE,
-            halign: HorizontalAlign::Center,
+            size: Size::Px(DEFAULT_SIZE),
+            halign: HAlign::Center,
             valign: VerticalAlign::Middle,
+            hanging_punctuation: false,
+            outlines: None,
+            hinting: Hinting::None,
+            antialiasing: Antialiasing::Grayscale,
+            opacity: 1.0,
+            blend_mode: BlendMode::SourceOver,
+            missing_glyphs: MissingGlyphs::Tofu,
+            on_missing_glyph: None,
+            control_chars: ControlChars::Keep,
+            nfc: false,
+            stroke: None,
+            decorations: Vec::default(),
+            glow: None,
+            emboss: None,
+            pattern: None,
+            regions: Vec::default(),
+            background: None,
+            tracking: Size::Px(0.0),
+            horizontal_scale: HorizontalScale::default(),
+            truncation: None,
+            fade_overflow: None,
+            decoration_spans: Vec::default(),
+            direction: Direction::default(),
+            inline_boxes: Vec::default(),
+            exclusions: Vec::default(),
+            shape: None,
+            stripes: None,
+            color_spans: Vec::default(),
+            scale_factor: 1.0,
+            dpi: DEFAULT_DPI,
+            root_size: DEFAULT_SIZE,
+            pixel_rounding: PixelRounding::None,
             font, text,
         }
     }
 
     pub fn bounds_inner(self, bounds: Rect) -> Builder<'f, 't, InnerBounds> {
-        Builder {
-            font: self.font,
-            text: self.text,
-            color: self.color,
-            size: self.size,
-            halign: self.halign,
-            valign: self.valign,
-            bounds: InnerBounds(bounds),
-        }
+        self.with_bounds(InnerBounds(bounds))
     }
 
     pub fn bounds_outer(self, bounds: Rect) -> Builder<'f, 't, OuterBounds> {
+        self.with_bounds(OuterBounds(bounds))
+    }
+
+    /// Builds the text box with bounds inset from the canvas by half the font size. The canvas size is in physical pixels, see [`scale_factor`](Self::scale_factor).
+    pub fn build<'l>(self, layout: &'l mut Layout, canvas_size: [f32; 2]) -> Result<TextBox<'f, 'l>, Error> {
+        let [canvas_width, canvas_height] = canvas_size.map(|length| length / self.scale_factor);
+        let canvas = Rect::from_xywh(0.0, 0.0, canvas_width, canvas_height).ok_or(Error::Rect { x: 0.0, y: 0.0, width: canvas_width, height: canvas_height })?;
+        let inner_bounds = canvas.inset(self.size_px() / 2.0, self.size_px() / 2.0).ok_or(Error::Inset { bounds: canvas, amount: self.size_px() / 2.0 })?;
+        Ok(self.bounds_inner(inner_bounds).build(layout))
+    }
+}
+
+impl<'f, 't, B: Bounds> Builder<'f, 't, B> {
+    fn with_bounds<C: Bounds>(self, bounds: C) -> Builder<'f, 't, C> {
         Builder {
             font: self.font,
             text: self.text,
@@ -89,17 +695,42 @@ impl<'f, 't> Builder<'f, 't, DefaultBounds> {
             size: self.size,
             halign: self.halign,
             valign: self.valign,
-            bounds: OuterBounds(bounds),
+            hanging_punctuation: self.hanging_punctuation,
+            outlines: self.outlines,
+            hinting: self.hinting,
+            antialiasing: self.antialiasing,
+            opacity: self.opacity,
+            blend_mode: self.blend_mode,
+            missing_glyphs: self.missing_glyphs,
+            on_missing_glyph: self.on_missing_glyph,
+            control_chars: self.control_chars,
+            nfc: self.nfc,
+            stroke: self.stroke,
+            decorations: self.decorations,
+            glow: self.glow,
+            emboss: self.emboss,
+            pattern: self.pattern,
+            regions: self.regions,
+            background: self.background,
+            tracking: self.tracking,
+            horizontal_scale: self.horizontal_scale,
+            truncation: self.truncation,
+            fade_overflow: self.fade_overflow,
+            decoration_spans: self.decoration_spans,
+            direction: self.direction,
+            inline_boxes: self.inline_boxes,
+            exclusions: self.exclusions,
+            shape: self.shape,
+            stripes: self.stripes,
+            color_spans: self.color_spans,
+            scale_factor: self.scale_factor,
+            dpi: self.dpi,
+            root_size: self.root_size,
+            pixel_rounding: self.pixel_rounding,
+            bounds,
         }
     }
 
-    pub fn build<'l>(self, layout: &'l mut Layout, [canvas_width, canvas_height]: [f32; 2]) -> Result<TextBox<'f, 'l>, Error> {
-        let inner_bounds = Rect::from_xywh(0.0, 0.0, canvas_width, canvas_height).ok_or(Error::Rect)?.inset(self.size / 2.0, self.size / 2.0).ok_or(Error::Inset)?;
-        Ok(self.bounds_inner(inner_bounds).build(layout))
-    }
-}
-
-impl<'f, 't, B: Bounds> Builder<'f, 't, B> {
     pub fn color(self, color: impl IntoColor) -> Self {
         Self {
             color: color.into_color_u8(),
@@ -107,59 +738,689 @@ impl<'f, 't, B: Bounds> Builder<'f, 't, B> {
         }
     }
 
-    pub fn size(self, size: f32) -> Self {
-        Self { size, ..self }
+    pub fn try_color(self, color: impl TryIntoColor) -> Result<Self, Error> {
+        Ok(Self {
+            color: color.try_into_color_u8()?,
+            ..self
+        })
+    }
+
+    /// Sets the font size, in pixels or a physical unit like points.
+    pub fn size(self, size: impl Into<Size>) -> Self {
+        Self { size: size.into(), ..self }
+    }
+
+    /// Sets the output resolution used to convert physical units to pixels, e.g. `300.0` for print. Defaults to [`DEFAULT_DPI`].
+    pub fn dpi(self, dpi: f32) -> Self {
+        Self { dpi, ..self }
+    }
+
+    /// Sets the size that [`Size::Rem`] is relative to, e.g. the base size of a theme. Defaults to [`DEFAULT_SIZE`].
+    pub fn root_size(self, root_size: f32) -> Self {
+        Self { root_size, ..self }
+    }
+
+    /// The font size in pixels.
+    fn size_px(&self) -> f32 {
+        self.size.to_px(self.dpi, self.root_size, self.root_size)
     }
 
-    pub fn halign(self, halign: HorizontalAlign) -> Self {
-        Self { halign, ..self }
+    /// A length in pixels, with [`Size::Em`] relative to the font size.
+    fn px(&self, length: Size) -> f32 {
+        length.to_px(self.dpi, self.size_px(), self.root_size)
     }
 
-    pub fn valign(self, valign: VerticalAlign) -> Self {
-        Self { valign, ..self }
+    /// Sets the horizontal alignment. [`HAlign::Start`] and [`HAlign::End`] are resolved using the base direction of the text when the text box is built.
+    pub fn halign(self, halign: impl Into<HAlign>) -> Self {
+        Self { halign: halign.into(), ..self }
+    }
+
+    /// Overrides the base direction of the text, which is otherwise determined by its first strongly directional character.
+    pub fn direction(self, direction: Direction) -> Self {
+        Self { direction, ..self }
+    }
+
+    pub fn valign(self, valign: impl Into<VerticalAlign>) -> Self {
+        Self { valign: valign.into(), ..self }
+    }
+
+    pub fn hanging_punctuation(self, hanging_punctuation: bool) -> Self {
+        Self { hanging_punctuation, ..self }
+    }
+
+    pub fn outlines(self, font_data: &'f [u8], threshold: f32) -> Self {
+        Self { outlines: Some((font_data, threshold)), ..self }
+    }
+
+    pub fn hinting(self, hinting: Hinting) -> Self {
+        Self { hinting, ..self }
+    }
+
+    pub fn antialiasing(self, antialiasing: Antialiasing) -> Self {
+        Self { antialiasing, ..self }
+    }
+
+    pub fn opacity(self, opacity: f32) -> Self {
+        Self { opacity, ..self }
+    }
+
+    pub fn blend_mode(self, blend_mode: BlendMode) -> Self {
+        Self { blend_mode, ..self }
+    }
+
+    pub fn missing_glyphs(self, missing_glyphs: MissingGlyphs) -> Self {
+        Self { missing_glyphs, ..self }
+    }
+
+    pub fn on_missing_glyph(self, on_missing_glyph: &'f dyn Fn(char)) -> Self {
+        Self { on_missing_glyph: Some(on_missing_glyph), ..self }
+    }
+
+    pub fn control_chars(self, control_chars: ControlChars) -> Self {
+        Self { control_chars, ..self }
+    }
+
+    pub fn nfc(self, nfc: bool) -> Self {
+        Self { nfc, ..self }
+    }
+
+    pub fn stroke(self, color: impl IntoColor, width: impl Into<Size>) -> Self {
+        Self { stroke: Some((Some(color.into_color_u8()), width.into())), ..self }
+    }
+
+    pub fn decoration(mut self, decoration: Decoration) -> Self {
+        self.decorations.push(decoration);
+        self
+    }
+
+    pub fn glow(self, color: impl IntoColor, radius: f32) -> Self {
+        Self { glow: Some((color.into_color_u8(), radius)), ..self }
+    }
+
+    /// Lights the inner edges of the glyphs from the top left. A negative `depth` makes the text look pressed in instead of raised.
+    pub fn emboss(self, highlight: impl IntoColor, shadow: impl IntoColor, depth: f32) -> Self {
+        Self { emboss: Some((highlight.into_color_u8(), shadow.into_color_u8(), depth)), ..self }
+    }
+
+    /// Fills the glyphs with the given pattern instead of the text color.
+    pub fn pattern(self, pattern: &'f Pixmap, fit: PatternFit) -> Self {
+        Self { pattern: Some((pattern, fit)), ..self }
+    }
+
+    /// Tags a byte range of the text, e.g. a link target. See `TextBox::region_rects`.
+    ///
+    /// Byte offsets are interpreted as for [`color_range`](Self::color_range).
+    pub fn region(mut self, range: Range<usize>, tag: impl Into<String>) -> Self {
+        self.regions.push((range, tag.into()));
+        self
+    }
+
+    /// Draws a nine-slice image behind the text, stretched to the outer rect plus `padding` on each side.
+    pub fn background(self, nine_slice: NineSlice<'f>, padding: impl Into<Size>) -> Self {
+        Self { background: Some((nine_slice, padding.into())), ..self }
+    }
+
+    /// Adds extra space after each glyph, or removes space if negative. This is applied after line breaking, so it does not cause lines to wrap differently.
+    pub fn tracking(self, tracking: impl Into<Size>) -> Self {
+        Self { tracking: tracking.into(), ..self }
+    }
+
+    /// Stretches or condenses the glyphs horizontally. Like tracking, this is applied after line breaking.
+    pub fn horizontal_scale(self, horizontal_scale: HorizontalScale) -> Self {
+        Self { horizontal_scale, ..self }
+    }
+
+    /// Synthesizes a condensed or expanded width by scaling the glyphs and their advances. Replaces any horizontal scale set previously.
+    pub fn font_stretch(self, stretch: FontStretch) -> Self {
+        Self { horizontal_scale: stretch.into(), ..self }
+    }
+
+    /// Shortens each line of the text that doesn't fit the bounds width, replacing the removed part with an ellipsis.
+    pub fn truncate(self, truncation: Truncation) -> Self {
+        Self { truncation: Some(truncation), ..self }
+    }
+
+    /// Fades out text that overflows the right or bottom edge of the bounds over the last `distance` pixels, instead of letting it overflow.
+    pub fn fade_overflow(self, distance: f32) -> Self {
+        Self { fade_overflow: Some(distance), ..self }
+    }
+
+    /// Decorates only the given byte range of the text, e.g. a wavy underline for a spelling mistake.
+    ///
+    /// Byte offsets are interpreted as for [`color_range`](Self::color_range).
+    pub fn decorate_range(mut self, range: Range<usize>, decoration: Decoration) -> Self {
+        self.decoration_spans.push((range, decoration));
+        self
+    }
+
+    /// Draws the glyphs whose text starts within the given byte range in a different color. Later ranges take precedence over earlier ones.
+    ///
+    /// Byte offsets refer to the text after preprocessing such as newline normalization.
+    /// Truncation and inline boxes insert text that shifts these offsets, so byte ranges can't be combined with [`truncate`](Self::truncate) or [`inline_box`](Self::inline_box).
+    pub fn color_range(mut self, range: Range<usize>, color: impl IntoColor) -> Self {
+        self.color_spans.push((range, color.into_color_u8()));
+        self
+    }
+
+    /// Marks the given byte range as IME composition (preedit) text, which is conventionally drawn with a dotted underline. The underline defaults to the text color.
+    pub fn composition(self, range: Range<usize>, color: Option<ColorU8>) -> Self {
+        self.decorate_range(range, Decoration { color, ..Decoration::new(DecorationLine::Underline, LineStyle::Dotted) })
+    }
+
+    /// Reserves space for a `width`×`height` box at the next U+FFFC OBJECT REPLACEMENT CHARACTER in the text which doesn't have a box yet,
+    /// e.g. for positioning buttons or avatars inline with wrapped text. The box's position after layout is available from [`TextBox::inline_box_rects`].
+    ///
+    /// The box doesn't make its line taller, so its height should fit within the line.
+    pub fn inline_box(mut self, width: f32, height: f32) -> Self {
+        self.inline_boxes.push([width, height]);
+        self
+    }
+
+    /// Flows the text around a rectangle, e.g. a figure floated in a corner. Each line is broken to fit the widest parts of the bounds left free by exclusions,
+    /// filling the free segments of a line from left to right.
+    ///
+    /// Text flowed around exclusions starts at the top of the bounds regardless of vertical alignment, and tracking and horizontal scaling are not applied to it.
+    pub fn exclude(mut self, exclusion: Rect) -> Self {
+        self.exclusions.push(exclusion);
+        self
+    }
+
+    /// Flows the text into a closed shape, e.g. a circle, instead of the full width of the bounds. Each line is broken to fit the parts of the shape it crosses,
+    /// filling them from left to right. Curves are approximated with line segments.
+    ///
+    /// Like with [`exclude`](Self::exclude), the text starts at the top of the bounds and tracking and horizontal scaling are not applied.
+    /// Text that doesn't fit into the shape overflows below the bounds across their full width.
+    pub fn shape(self, shape: Path) -> Self {
+        Self { shape: Some(shape), ..self }
+    }
+
+    /// Draws alternating background colors behind the laid out lines, starting with `even` for the first line, e.g. for rendering tables, logs, and lists.
+    pub fn zebra_stripes(self, even: impl IntoColor, odd: impl IntoColor, width: StripeWidth) -> Self {
+        Self { stripes: Some(([even.into_color_u8(), odd.into_color_u8()], width)), ..self }
+    }
+
+    /// Multiplies all lengths given to the builder, such as the size, bounds, tracking, and stroke and decoration thicknesses, by this factor when the text box is built,
+    /// e.g. `2.0` for a HiDPI display. Lengths given to the builder are then in logical pixels, while the built text box and the canvas are in physical pixels.
+    ///
+    /// Glyphs are rasterized and cached at the scaled size, so they stay sharp.
+    pub fn scale_factor(self, scale_factor: f32) -> Self {
+        Self { scale_factor, ..self }
+    }
+
+    /// Aligns glyph positions and the reported rects to whole pixels, so borders drawn around the text don't show seams.
+    pub fn pixel_rounding(self, pixel_rounding: PixelRounding) -> Self {
+        Self { pixel_rounding, ..self }
     }
 }
 
 impl<'f, 't> Builder<'f, 't, InnerBounds> {
-    pub fn build<'l>(self, layout: &'l mut Layout) -> TextBox<'f, 'l> {
+    /// Converts all lengths from logical to physical pixels, see [`scale_factor`](Self::scale_factor).
+    fn scaled(self) -> Self {
+        let scale = self.scale_factor;
+        let [size, stroke_width, padding, tracking] = [
+            self.size_px(),
+            self.stroke.map_or(0.0, |(_, width)| self.px(width)),
+            self.background.map_or(0.0, |(_, padding)| self.px(padding)),
+            self.px(self.tracking),
+        ].map(|length| Size::Px(length * scale));
+        let scale_rect = |rect: Rect| rect.transform(Transform::from_scale(scale, scale)).unwrap_or(rect);
+        let scale_decoration = |decoration: Decoration| Decoration { thickness: decoration.thickness.map(|thickness| thickness * scale), ..decoration };
+        Self {
+            bounds: InnerBounds(scale_rect(self.bounds.0)),
+            size,
+            valign: match self.valign {
+                VerticalAlign::Baseline(baseline) => VerticalAlign::Baseline(baseline * scale),
+                valign => valign,
+            },
+            outlines: self.outlines.map(|(font_data, threshold)| (font_data, threshold * scale)),
+            stroke: self.stroke.map(|(color, _)| (color, stroke_width)),
+            decorations: self.decorations.into_iter().map(scale_decoration).collect(),
+            glow: self.glow.map(|(color, radius)| (color, radius * scale)),
+            emboss: self.emboss.map(|(highlight, shadow, depth)| (highlight, shadow, depth * scale)),
+            background: self.background.map(|(nine_slice, _)| (nine_slice, padding)),
+            tracking,
+            fade_overflow: self.fade_overflow.map(|distance| distance * scale),
+            decoration_spans: self.decoration_spans.into_iter().map(|(range, decoration)| (range, scale_decoration(decoration))).collect(),
+            inline_boxes: self.inline_boxes.into_iter().map(|size| size.map(|length| length * scale)).collect(),
+            exclusions: self.exclusions.into_iter().map(scale_rect).collect(),
+            shape: self.shape.map(|shape| shape.clone().transform(Transform::from_scale(scale, scale)).unwrap_or(shape)),
+            scale_factor: 1.0,
+            ..self
+        }
+    }
+
+    fn reset_layout(&self, layout: &mut Layout) {
+        self.reset_layout_with_width(layout, self.bounds.0.width());
+    }
+
+    fn reset_layout_with_width(&self, layout: &mut Layout, max_width: f32) {
         layout.reset(&LayoutSettings {
             x: self.bounds.0.x(),
             y: self.bounds.0.y(),
-            max_width: Some(self.bounds.0.width()),
+            max_width: Some(max_width),
             max_height: Some(self.bounds.0.height()),
-            horizontal_align: self.halign,
-            vertical_align: self.valign,
+            horizontal_align: self.halign.resolve(self.direction, self.text),
+            vertical_align: match self.valign {
+                VerticalAlign::Top | VerticalAlign::Baseline(_) | VerticalAlign::MiddleCapHeight | VerticalAlign::MiddleXHeight => fontdue::layout::VerticalAlign::Top,
+                VerticalAlign::Middle => fontdue::layout::VerticalAlign::Middle,
+                VerticalAlign::Bottom => fontdue::layout::VerticalAlign::Bottom,
+            },
             ..LayoutSettings::default()
         });
-        layout.append(std::slice::from_ref(self.font), &TextStyle::new(self.text, self.size, 0));
+    }
+
+    fn into_text_box<'l>(self, layout: &'l mut Layout) -> TextBox<'f, 'l> {
+        debug_assert!(
+            self.regions.is_empty() && self.color_spans.is_empty() && self.decoration_spans.is_empty() || self.truncation.is_none() && self.inline_boxes.is_empty(),
+            "byte ranges can't be combined with truncation or inline boxes",
+        );
+        let stroke = self.stroke.map(|(color, width)| (color.unwrap_or(self.color), self.px(width)));
+        let background = self.background.map(|(nine_slice, padding)| (nine_slice, self.px(padding)));
+        let tracking = self.px(self.tracking);
         TextBox {
             font: self.font,
+            glyphs: Vec::default(),
+            lines: Vec::default(),
+            height: 0.0,
             color: self.color,
-            size: self.size,
-            halign: self.halign,
+            size: self.size_px(),
+            halign: self.halign.resolve(self.direction, self.text),
             valign: self.valign,
+            hanging_punctuation: self.hanging_punctuation,
+            outlines: self.outlines,
             inner_bounds: self.bounds.0,
+            hinting: self.hinting,
+            antialiasing: self.antialiasing,
+            opacity: self.opacity,
+            blend_mode: self.blend_mode,
+            missing_glyphs: self.missing_glyphs,
+            on_missing_glyph: self.on_missing_glyph,
+            control_chars: self.control_chars,
+            nfc: self.nfc,
+            stroke,
+            decorations: self.decorations,
+            glow: self.glow,
+            emboss: self.emboss,
+            pattern: self.pattern,
+            regions: self.regions,
+            background,
+            tracking,
+            layout_width: self.bounds.0.width(),
+            scale_x: 1.0,
+            horizontal_scale: self.horizontal_scale,
+            truncation: self.truncation,
+            fade_overflow: self.fade_overflow,
+            decoration_spans: self.decoration_spans,
+            inline_boxes: self.inline_boxes,
+            exclusions: self.exclusions,
+            shape: self.shape,
+            stripes: self.stripes,
+            color_spans: self.color_spans,
+            pixel_rounding: self.pixel_rounding,
             layout,
+            deferred_text: None,
+            text: String::default(),
+            inline_box_glyphs: Vec::default(),
+            glyph_colors: Vec::default(),
+            missing_glyph_chars: Vec::default(),
+            control_char: None,
+            trailing_cr: false,
+            y_offset: 0.0,
+        }
+    }
+
+    pub fn build<'l>(self, layout: &'l mut Layout) -> TextBox<'f, 'l> {
+        if self.scale_factor != 1.0 { return self.scaled().build(layout) }
+        let text = self.text;
+        self.reset_layout(layout);
+        let mut text_box = self.into_text_box(layout);
+        text_box.append(text);
+        text_box
+    }
+
+    pub fn build_cached<'l>(self, layout: &'l mut Layout, cache: &mut LayoutCache) -> TextBox<'f, 'l> {
+        if self.scale_factor != 1.0 { return self.scaled().build_cached(layout, cache) }
+        let key = LayoutKey {
+            text: Arc::from(self.text),
+            size: self.size_px().to_bits(),
+            bounds: [self.bounds.0.x(), self.bounds.0.y(), self.bounds.0.width(), self.bounds.0.height()].map(f32::to_bits),
+            halign: match self.halign.resolve(self.direction, self.text) {
+                HorizontalAlign::Left => 0,
+                HorizontalAlign::Center => 1,
+                HorizontalAlign::Right => 2,
+            },
+            valign: match self.valign {
+                VerticalAlign::Top => (0, 0),
+                VerticalAlign::Middle => (1, 0),
+                VerticalAlign::Bottom => (2, 0),
+                VerticalAlign::Baseline(baseline) => (3, baseline.to_bits()),
+                VerticalAlign::MiddleCapHeight => (4, 0),
+                VerticalAlign::MiddleXHeight => (5, 0),
+            },
+            hanging_punctuation: self.hanging_punctuation,
+            hinting: self.hinting,
+            missing_glyphs: self.missing_glyphs,
+            control_chars: self.control_chars,
+            nfc: self.nfc,
+            tracking: self.px(self.tracking).to_bits(),
+            horizontal_scale: match self.horizontal_scale {
+                HorizontalScale::Factor(factor) => (0, factor.to_bits()),
+                HorizontalScale::Fill => (1, 0),
+            },
+            truncation: self.truncation,
+            inline_boxes: self.inline_boxes.iter().map(|size| size.map(f32::to_bits)).collect(),
+            exclusions: self.exclusions.iter().map(|exclusion| [exclusion.x(), exclusion.y(), exclusion.width(), exclusion.height()].map(f32::to_bits)).collect(),
+            shape: self.shape.as_ref().map(|shape| (
+                shape.verbs().iter().map(|&verb| verb as u8).collect(),
+                shape.points().iter().map(|point| [point.x, point.y].map(f32::to_bits)).collect(),
+            )),
+            pixel_rounding: self.pixel_rounding,
+            font_hash: self.font.file_hash(),
+        };
+        if let Some((LayoutKey { text, .. }, CachedLayout { laid_out_text, inline_box_glyphs, glyphs, lines, height, y_offset, missing_glyphs, control_char })) = cache.layouts.get_key_value(&key) {
+            cache.hits += 1;
+            let text = Arc::clone(text);
+            if let Some(on_missing_glyph) = self.on_missing_glyph {
+                substitute_missing_glyphs(self.font, self.missing_glyphs, &text, on_missing_glyph);
+            }
+            self.reset_layout(layout);
+            let mut text_box = self.into_text_box(layout);
+            text_box.missing_glyph_chars.clone_from(missing_glyphs);
+            text_box.control_char = *control_char;
+            text_box.glyphs.clone_from(glyphs);
+            text_box.lines.clone_from(lines);
+            text_box.height = *height;
+            text_box.y_offset = *y_offset;
+            text_box.deferred_text = Some(text);
+            text_box.text.clone_from(laid_out_text);
+            text_box.inline_box_glyphs.clone_from(inline_box_glyphs);
+            text_box.update_glyph_colors();
+            text_box
+        } else {
+            let text_box = if self.inline_boxes.is_empty() {
+                // the preprocessed run doesn't depend on the bounds, so it's shared with measurements and layouts of the same text in other bounds
+                let run = cache.run(self.text, self.run_key(), || self.prepare_run());
+                self.reset_layout(layout);
+                let mut text_box = self.into_text_box(layout);
+                text_box.append_run(run, &key.text);
+                text_box
+            } else {
+                cache.misses += 1;
+                self.build(layout)
+            };
+            cache.layouts.insert(key, CachedLayout {
+                glyphs: text_box.glyphs.clone(),
+                lines: text_box.lines.clone(),
+                height: text_box.height,
+                y_offset: text_box.y_offset,
+                missing_glyphs: text_box.missing_glyph_chars.clone(),
+                control_char: text_box.control_char,
+                laid_out_text: text_box.text.clone(),
+                inline_box_glyphs: text_box.inline_box_glyphs.clone(),
+            });
+            text_box
         }
     }
 }
 
 impl<'f, 't> Builder<'f, 't, OuterBounds> {
-    fn bounds_inner(self, bounds: Rect) -> Builder<'f, 't, InnerBounds> {
-        Builder {
-            font: self.font,
-            text: self.text,
-            color: self.color,
-            size: self.size,
-            halign: self.halign,
-            valign: self.valign,
-            bounds: InnerBounds(bounds),
+    pub fn build<'l>(self, layout: &'l mut Layout) -> Result<TextBox<'f, 'l>, Error> {
+        let inner_bounds = self.bounds.0.inset(self.size_px() / 2.0, self.size_px() / 2.0).ok_or(Error::Inset { bounds: self.bounds.0, amount: self.size_px() / 2.0 })?;
+        Ok(self.with_bounds(InnerBounds(inner_bounds)).build(layout))
+    }
+}
+
+/// Like `Itertools::unique`, which requires `std`.
+fn unique<I: IntoIterator>(iter: I) -> impl Iterator<Item = I::Item> + Clone
+where I::IntoIter: Clone, I::Item: Eq + Hash + Clone {
+    let mut seen = HashSet::new();
+    iter.into_iter().filter(move |item| seen.insert(item.clone()))
+}
+
+fn union(a: Option<Rect>, b: Option<Rect>) -> Option<Rect> {
+    match (a, b) {
+        (Some(a), Some(b)) => Rect::from_ltrb(a.left().min(b.left()), a.top().min(b.top()), a.right().max(b.right()), a.bottom().max(b.bottom())),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Builds a rectangle path with the given corner radii, clockwise from the top left.
+fn rounded_rect(rect: Rect, [top_left, top_right, bottom_right, bottom_left]: [f32; 4]) -> Option<Path> {
+    // control point distance for approximating a quarter circle with a cubic Bézier curve
+    const KAPPA: f32 = 0.552_284_8;
+
+    let max_radius = rect.width().min(rect.height()) / 2.0;
+    let [top_left, top_right, bottom_right, bottom_left] = [top_left, top_right, bottom_right, bottom_left].map(|radius| radius.clamp(0.0, max_radius));
+    let (left, top, right, bottom) = (rect.left(), rect.top(), rect.right(), rect.bottom());
+    let mut path = PathBuilder::new();
+    path.move_to(left + top_left, top);
+    path.line_to(right - top_right, top);
+    path.cubic_to(right - top_right * (1.0 - KAPPA), top, right, top + top_right * (1.0 - KAPPA), right, top + top_right);
+    path.line_to(right, bottom - bottom_right);
+    path.cubic_to(right, bottom - bottom_right * (1.0 - KAPPA), right - bottom_right * (1.0 - KAPPA), bottom, right - bottom_right, bottom);
+    path.line_to(left + bottom_left, bottom);
+    path.cubic_to(left + bottom_left * (1.0 - KAPPA), bottom, left, bottom - bottom_left * (1.0 - KAPPA), left, bottom - bottom_left);
+    path.line_to(left, top + top_left);
+    path.cubic_to(left, top + top_left * (1.0 - KAPPA), left + top_left * (1.0 - KAPPA), top, left + top_left, top);
+    path.close();
+    path.finish()
+}
+
+/// The width of a line as laid out by fontdue, which rounds advances up and doesn't apply kerning.
+fn line_width(font: &Font, line: &str, size: f32) -> f32 {
+    line.chars().map(|c| font.metrics(c, size).advance_width.ceil()).sum()
+}
+
+fn valign_offset(font: &Font, size: f32, valign: VerticalAlign, bounds: Rect, lines: &[LinePosition]) -> f32 {
+    let (Some(first_line), Some(last_line)) = (lines.first(), lines.last()) else { return 0.0 };
+    let visual_height = match valign {
+        VerticalAlign::Top | VerticalAlign::Middle | VerticalAlign::Bottom => return 0.0,
+        VerticalAlign::Baseline(baseline) => return bounds.y() + baseline - first_line.baseline_y,
+        VerticalAlign::MiddleCapHeight => Metrics::new(font, size).map_or(0.0, |metrics| metrics.cap_height),
+        VerticalAlign::MiddleXHeight => Metrics::new(font, size).map_or(0.0, |metrics| metrics.x_height),
+    };
+    let top = first_line.baseline_y - visual_height;
+    let bottom = last_line.baseline_y;
+    (bounds.y() + bounds.height() / 2.0 - (top + bottom) / 2.0).round()
+}
+
+fn normalize_newlines(text: &str) -> Cow<'_, str> {
+    if !text.contains(['\r', '\u{85}', '\u{2028}', '\u{2029}']) { return Cow::Borrowed(text) }
+    Cow::Owned(text.replace("\r\n", "\n").replace(['\r', '\u{85}', '\u{2028}', '\u{2029}'], "\n"))
+}
+
+/// Drops the LF of a CRLF split across two appends, since the CR was already normalized to a line break. `trailing_cr` tracks whether the text appended so far ends with a CR.
+fn continue_crlf<'a>(trailing_cr: &mut bool, text: &'a str) -> &'a str {
+    let text = if *trailing_cr { text.strip_prefix('\n').unwrap_or(text) } else { text };
+    if !text.is_empty() {
+        *trailing_cr = text.ends_with('\r');
+    }
+    text
+}
+
+fn is_control_char(c: char) -> bool {
+    (c.is_control() && c != '\n' && c != '\t') || matches!(c,
+        '\u{ad}' | '\u{200b}'..='\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2060}'..='\u{2064}' | '\u{2066}'..='\u{206f}' | '\u{feff}' | '\u{fff9}'..='\u{fffb}'
+    )
+}
+
+fn handle_control_chars(policy: ControlChars, text: &str) -> Result<Cow<'_, str>, (char, usize)> {
+    let Some((byte_offset, c)) = text.char_indices().find(|&(_, c)| is_control_char(c)) else { return Ok(Cow::Borrowed(text)) };
+    Ok(match policy {
+        ControlChars::Keep => Cow::Borrowed(text),
+        ControlChars::Strip => Cow::Owned(text.chars().filter(|&c| !is_control_char(c)).collect()),
+        ControlChars::Escape => {
+            let mut escaped = String::with_capacity(text.len());
+            for c in text.chars() {
+                match c {
+                    '\0'..='\u{1f}' if is_control_char(c) => escaped.push(char::from_u32(0x2400 + u32::from(c)).unwrap_or(char::REPLACEMENT_CHARACTER)),
+                    '\u{7f}' => escaped.push('\u{2421}'),
+                    _ if is_control_char(c) => escaped.push_str(&format!("<U+{:04X}>", u32::from(c))),
+                    _ => escaped.push(c),
+                }
+            }
+            Cow::Owned(escaped)
+        }
+        ControlChars::Error => return Err((c, byte_offset)),
+    })
+}
+
+fn substitute_missing_glyphs<'a>(font: &Font, policy: MissingGlyphs, text: &'a str, mut on_missing_glyph: impl FnMut(char)) -> Cow<'a, str> {
+    let is_missing = |c: char| !c.is_control() && !font.has_glyph(c);
+    if !text.chars().any(is_missing) { return Cow::Borrowed(text) }
+    let mut substituted = String::with_capacity(text.len());
+    for c in text.chars() {
+        if is_missing(c) {
+            on_missing_glyph(c);
+            match policy {
+                MissingGlyphs::Tofu => substituted.push(c),
+                MissingGlyphs::Skip => {}
+                MissingGlyphs::Replace => substituted.push(char::REPLACEMENT_CHARACTER),
+            }
+        } else {
+            substituted.push(c);
         }
     }
+    Cow::Owned(substituted)
+}
 
-    pub fn build<'l>(self, layout: &'l mut Layout) -> Result<TextBox<'f, 'l>, Error> {
-        let inner_bounds = self.bounds.0.inset(self.size / 2.0, self.size / 2.0).ok_or(Error::Inset)?;
-        Ok(self.bounds_inner(inner_bounds).build(layout))
+fn hanging_fraction(c: char) -> f32 {
+    match c {
+        '"' | '\'' | '‘' | '’' | '‚' | '“' | '”' | '„' | '.' | ',' => 1.0,
+        '-' | '‐' | '‑' | '–' | '—' | '«' | '»' | '‹' | '›' => 0.5,
+        ':' | ';' | '!' | '?' => 0.25,
+        _ => 0.0,
+    }
+}
+
+fn hang_punctuation(font: &Font, halign: HorizontalAlign, lines: &[LinePosition], glyphs: &mut [GlyphPosition]) {
+    for line in lines {
+        let Some(line_glyphs) = glyphs.get_mut(line.glyph_start..=line.glyph_end) else { continue };
+        let hang = |glyph: Option<&GlyphPosition>| glyph.map_or(0.0, |glyph| hanging_fraction(glyph.parent) * font.metrics_indexed(glyph.key.glyph_index, glyph.key.px).advance_width);
+        let start = hang(line_glyphs.iter().find(|glyph| !glyph.parent.is_whitespace()));
+        let end = hang(line_glyphs.iter().rfind(|glyph| !glyph.parent.is_whitespace()));
+        let offset = match halign {
+            HorizontalAlign::Left => -start,
+            HorizontalAlign::Center => (end - start) / 2.0,
+            HorizontalAlign::Right => end,
+        }.round();
+        for glyph in line_glyphs {
+            glyph.x += offset;
+        }
+    }
+}
+
+/// The pen position at the start of a line and the unscaled, untracked advance width up to its last visible glyph, along with the number of gaps between its glyphs.
+fn line_span(font: &Font, line_glyphs: &[GlyphPosition]) -> Option<(f32, f32, usize)> {
+    let first = line_glyphs.first()?;
+    let origin = |glyph: &GlyphPosition| glyph.x - font.metrics_indexed(glyph.key.glyph_index, glyph.key.px).xmin as f32;
+    let start = origin(first);
+    let Some(gaps) = line_glyphs.iter().rposition(|glyph| !glyph.parent.is_whitespace()) else { return Some((start, 0.0, 0)) };
+    let last = &line_glyphs[gaps];
+    let end = origin(last) + font.metrics_indexed(last.key.glyph_index, last.key.px).advance_width;
+    Some((start, end - start, gaps))
+}
+
+/// Scales and spaces out the glyphs of each line and realigns the line within the bounds, since fontdue aligned it using its natural width.
+fn apply_spacing(font: &Font, tracking: f32, scale_x: f32, halign: HorizontalAlign, bounds: Rect, lines: &mut [LinePosition], glyphs: &mut [GlyphPosition]) {
+    let align = match halign {
+        HorizontalAlign::Left => 0.0,
+        HorizontalAlign::Center => 0.5,
+        HorizontalAlign::Right => 1.0,
+    };
+    for line in lines {
+        let Some(line_glyphs) = glyphs.get_mut(line.glyph_start..=line.glyph_end) else { continue };
+        let Some((start, width, gaps)) = line_span(font, line_glyphs) else { continue };
+        let padding = bounds.width() - (width * scale_x + tracking * gaps as f32);
+        let new_start = bounds.x() + (padding * align).floor();
+        for (idx, glyph) in line_glyphs.iter_mut().enumerate() {
+            glyph.x = new_start + (glyph.x - start) * scale_x + tracking * idx as f32;
+            if scale_x != 1.0 {
+                glyph.width = scaled_width(glyph.width, scale_x);
+            }
+        }
+        line.padding = padding;
+    }
+}
+
+fn scaled_width(width: usize, scale_x: f32) -> usize {
+    (width as f32 * scale_x).ceil() as usize
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct LayoutKey {
+    text: Arc<str>,
+    size: u32,
+    bounds: [u32; 4],
+    halign: u8,
+    valign: (u8, u32),
+    hanging_punctuation: bool,
+    hinting: Hinting,
+    missing_glyphs: MissingGlyphs,
+    control_chars: ControlChars,
+    nfc: bool,
+    tracking: u32,
+    horizontal_scale: (u8, u32),
+    truncation: Option<Truncation>,
+    inline_boxes: Vec<[u32; 2]>,
+    exclusions: Vec<[u32; 4]>,
+    shape: Option<(Vec<u8>, Vec<[u32; 2]>)>,
+    pixel_rounding: PixelRounding,
+    font_hash: usize,
+}
+
+struct CachedLayout {
+    laid_out_text: String,
+    inline_box_glyphs: Vec<Range<usize>>,
+    glyphs: Vec<GlyphPosition>,
+    lines: Vec<LinePosition>,
+    height: f32,
+    y_offset: f32,
+    missing_glyphs: Vec<char>,
+    control_char: Option<(char, usize)>,
+}
+
+#[derive(Default)]
+pub struct LayoutCache {
+    layouts: HashMap<LayoutKey, CachedLayout>,
+    /// Preprocessed runs of text and their measurements, shared by measuring and by all layouts of the same run of text.
+    runs: HashMap<String, HashMap<RunKey, Run>>,
+    hits: u64,
+    misses: u64,
+}
+
+impl LayoutCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.layouts.len() + self.runs.values().map(HashMap::len).sum::<usize>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layouts.is_empty() && self.runs.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.layouts.clear();
+        self.runs.clear();
+    }
+
+    /// Hits count layouts and runs of text reused from the cache, misses count runs of text that had to be preprocessed and laid out.
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.len(),
+            bytes: self.layouts.iter().map(|(key, layout)| size_of::<(LayoutKey, CachedLayout)>() + key.text.len() + layout.laid_out_text.len() + layout.glyphs.len() * size_of::<GlyphPosition>() + layout.lines.len() * size_of::<LinePosition>()).sum::<usize>()
+                + self.runs.values().flat_map(HashMap::values).map(|run| size_of::<(RunKey, Run)>() + run.text.len()).sum::<usize>(),
+        }
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.hits = 0;
+        self.misses = 0;
     }
 }
 
@@ -167,71 +1428,762 @@ impl<'f, 't> Builder<'f, 't, OuterBounds> {
 pub struct TextBox<'f, 'l> {
     font: &'f Font,
     layout: &'l mut Layout,
+    deferred_text: Option<Arc<str>>,
+    /// The glyphs reserving space for each inline box.
+    inline_box_glyphs: Vec<Range<usize>>,
+    /// The color of each glyph, if any color spans are set.
+    glyph_colors: Vec<ColorU8>,
+    /// The text appended so far, after preprocessing, which glyph byte offsets refer to.
+    text: String,
+    missing_glyph_chars: Vec<char>,
+    control_char: Option<(char, usize)>,
+    trailing_cr: bool,
+    glyphs: Vec<GlyphPosition>,
+    lines: Vec<LinePosition>,
+    height: f32,
     inner_bounds: Rect,
+    y_offset: f32,
     color: ColorU8,
     size: f32,
     halign: HorizontalAlign,
     valign: VerticalAlign,
+    hanging_punctuation: bool,
+    outlines: Option<(&'f [u8], f32)>,
+    hinting: Hinting,
+    antialiasing: Antialiasing,
+    opacity: f32,
+    blend_mode: BlendMode,
+    missing_glyphs: MissingGlyphs,
+    on_missing_glyph: Option<&'f dyn Fn(char)>,
+    control_chars: ControlChars,
+    nfc: bool,
+    stroke: Option<(ColorU8, f32)>,
+    decorations: Vec<Decoration>,
+    glow: Option<(ColorU8, f32)>,
+    emboss: Option<(ColorU8, ColorU8, f32)>,
+    pattern: Option<(&'f Pixmap, PatternFit)>,
+    regions: Vec<(Range<usize>, String)>,
+    background: Option<(NineSlice<'f>, f32)>,
+    tracking: f32,
+    /// The width fontdue wrapped lines at, which can differ from the bounds width when fitting text.
+    layout_width: f32,
+    horizontal_scale: HorizontalScale,
+    /// The horizontal scale factor resolved from `horizontal_scale`.
+    scale_x: f32,
+    truncation: Option<Truncation>,
+    fade_overflow: Option<f32>,
+    decoration_spans: Vec<(Range<usize>, Decoration)>,
+    inline_boxes: Vec<[f32; 2]>,
+    exclusions: Vec<Rect>,
+    shape: Option<Path>,
+    stripes: Option<([ColorU8; 2], StripeWidth)>,
+    color_spans: Vec<(Range<usize>, ColorU8)>,
+    pixel_rounding: PixelRounding,
+}
+
+impl fmt::Debug for TextBox<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the layout is omitted since its results are copied into the text box
+        f.debug_struct("TextBox")
+            .field("font", &format_args!("Font {{ file_hash: {} }}", self.font.file_hash()))
+            .field("deferred_text", &self.deferred_text)
+            .field("inline_box_glyphs", &self.inline_box_glyphs)
+            .field("glyph_colors", &self.glyph_colors)
+            .field("text", &self.text)
+            .field("missing_glyph_chars", &self.missing_glyph_chars)
+            .field("control_char", &self.control_char)
+            .field("trailing_cr", &self.trailing_cr)
+            .field("glyphs", &self.glyphs)
+            .field("lines", &self.lines)
+            .field("height", &self.height)
+            .field("inner_bounds", &self.inner_bounds)
+            .field("y_offset", &self.y_offset)
+            .field("color", &self.color)
+            .field("size", &self.size)
+            .field("halign", &HAlign::from(self.halign))
+            .field("valign", &self.valign)
+            .field("hanging_punctuation", &self.hanging_punctuation)
+            .field("outlines", &self.outlines.map(|(_, threshold)| threshold))
+            .field("hinting", &self.hinting)
+            .field("antialiasing", &self.antialiasing)
+            .field("opacity", &self.opacity)
+            .field("blend_mode", &self.blend_mode)
+            .field("missing_glyphs", &self.missing_glyphs)
+            .field("on_missing_glyph", &self.on_missing_glyph.is_some())
+            .field("control_chars", &self.control_chars)
+            .field("nfc", &self.nfc)
+            .field("stroke", &self.stroke)
+            .field("decorations", &self.decorations)
+            .field("glow", &self.glow)
+            .field("emboss", &self.emboss)
+            .field("pattern", &self.pattern)
+            .field("regions", &self.regions)
+            .field("background", &self.background)
+            .field("tracking", &self.tracking)
+            .field("layout_width", &self.layout_width)
+            .field("horizontal_scale", &self.horizontal_scale)
+            .field("scale_x", &self.scale_x)
+            .field("truncation", &self.truncation)
+            .field("fade_overflow", &self.fade_overflow)
+            .field("decoration_spans", &self.decoration_spans)
+            .field("inline_boxes", &self.inline_boxes)
+            .field("exclusions", &self.exclusions)
+            .field("shape", &self.shape)
+            .field("stripes", &self.stripes)
+            .field("color_spans", &self.color_spans)
+            .field("pixel_rounding", &self.pixel_rounding)
+            .finish_non_exhaustive()
+    }
 }
 
 impl TextBox<'_, '_> {
-    #[must_use]
+    /// Copies the lines from `first_line` onward and their glyphs out of the layout and post-processes them, keeping the earlier ones.
+    ///
+    /// Everything is redone if the lines can't be updated independently, e.g. because the vertical alignment offset changed.
+    fn update_glyphs(&mut self, first_line: usize) {
+        let first_line = if self.flows()
+            || matches!(self.horizontal_scale, HorizontalScale::Fill)
+            || matches!(self.valign, VerticalAlign::Middle | VerticalAlign::Bottom)
+        { 0 } else { first_line.min(self.lines.len()) };
+        let y_offset = self.y_offset;
+        if self.flows() {
+            self.flow();
+            self.scale_x = 1.0;
+            self.y_offset = 0.0;
+        } else {
+            let layout_lines = self.layout.lines().map_or(&[][..], Vec::as_slice);
+            self.lines.truncate(first_line);
+            self.lines.extend_from_slice(layout_lines.get(first_line..).unwrap_or_default());
+            let start = if first_line == 0 { 0 } else { self.lines.get(first_line).map_or(self.layout.glyphs().len(), |line| line.glyph_start) };
+            self.glyphs.truncate(start);
+            self.glyphs.extend_from_slice(&self.layout.glyphs()[start..]);
+            self.height = self.layout.height();
+            self.scale_x = match self.horizontal_scale {
+                HorizontalScale::Factor(factor) => factor,
+                HorizontalScale::Fill => self.fill_scale(),
+            };
+            if self.tracking != 0.0 || self.scale_x != 1.0 || self.layout_width != self.inner_bounds.width() {
+                apply_spacing(self.font, self.tracking, self.scale_x, self.halign, self.inner_bounds, &mut self.lines[first_line..], &mut self.glyphs);
+            }
+            self.y_offset = valign_offset(self.font, self.size, self.valign, self.inner_bounds, &self.lines);
+        }
+        // a changed offset moves every line, so the earlier ones have to be copied out of the layout again
+        if first_line > 0 && self.y_offset != y_offset {
+            return self.update_glyphs(0)
+        }
+        let start = if first_line == 0 { 0 } else { self.lines.get(first_line).map_or(self.glyphs.len(), |line| line.glyph_start) };
+        if self.y_offset != 0.0 {
+            for glyph in &mut self.glyphs[start..] {
+                glyph.y += self.y_offset;
+            }
+        }
+        if self.hanging_punctuation {
+            hang_punctuation(self.font, self.halign, &self.lines[first_line..], &mut self.glyphs);
+        }
+        if self.hinting != Hinting::None || self.pixel_rounding != PixelRounding::None {
+            for glyph in &mut self.glyphs[start..] {
+                glyph.x = glyph.x.round();
+                glyph.y = glyph.y.round();
+            }
+        }
+        self.update_glyph_colors();
+    }
+
+    fn layout_text(&mut self, text: &str, report: bool) {
+        let text = continue_crlf(&mut self.trailing_cr, text);
+        let text = normalize_newlines(text);
+        let text = if self.nfc && !unicode_normalization::is_nfc(&text) { Cow::Owned(text.nfc().collect()) } else { text };
+        let text = match handle_control_chars(self.control_chars, &text) {
+            Ok(text) => text,
+            Err(control_char) => {
+                if report {
+                    self.control_char.get_or_insert(control_char);
+                }
+                text
+            }
+        };
+        // when laying out deferred text, the inline boxes have already been recorded
+        let mut inline_box = if report { self.inline_box_glyphs.len() } else { 0 };
+        let mut rest = &*text;
+        while let Some(idx) = rest.find(OBJECT_REPLACEMENT).filter(|_| inline_box < self.inline_boxes.len()) {
+            self.layout_segment(&rest[..idx], report);
+            let start = self.layout.glyphs().len();
+            for (run, px) in self.inline_box_runs(self.inline_boxes[inline_box][0]) {
+                self.layout.append(core::slice::from_ref(self.font), &TextStyle::new(&run, px, 0));
+                if report {
+                    self.text.push_str(&run);
+                }
+            }
+            if report {
+                self.inline_box_glyphs.push(start..self.layout.glyphs().len());
+            }
+            inline_box += 1;
+            rest = &rest[idx + OBJECT_REPLACEMENT.len_utf8()..];
+        }
+        self.layout_segment(rest, report);
+    }
+
+    fn layout_segment(&mut self, text: &str, report: bool) {
+        let missing_glyph_chars = &mut self.missing_glyph_chars;
+        let on_missing_glyph = self.on_missing_glyph;
+        let text = substitute_missing_glyphs(self.font, self.missing_glyphs, text, |c| if report {
+            if !missing_glyph_chars.contains(&c) {
+                missing_glyph_chars.push(c);
+            }
+            if let Some(on_missing_glyph) = on_missing_glyph {
+                on_missing_glyph(c);
+            }
+        });
+        let text = self.truncate(text);
+        if report {
+            self.text.push_str(&text);
+        }
+        self.layout.append(core::slice::from_ref(self.font), &TextStyle::new(&text, self.size, 0));
+    }
+
+    pub fn append(&mut self, text: &str) {
+        if let Some(deferred_text) = self.deferred_text.take() {
+            self.layout_text(&deferred_text, false);
+        }
+        self.layout_text(text, true);
+        // appending can only rewrap and realign the last line, so earlier lines are kept
+        self.update_glyphs(self.lines.len().saturating_sub(1));
+    }
+
+    /// Like [`append`](Self::append) for the whole text, but lays out a run that was already preprocessed with the same options.
+    fn append_run(&mut self, run: &Run, text: &str) {
+        if let Some(on_missing_glyph) = self.on_missing_glyph {
+            substitute_missing_glyphs(self.font, self.missing_glyphs, text, on_missing_glyph);
+        }
+        self.missing_glyph_chars.clone_from(&run.missing_glyphs);
+        self.control_char = run.control_char;
+        continue_crlf(&mut self.trailing_cr, text);
+        let laid_out_text = self.truncate(Cow::Borrowed(&run.text));
+        self.text.push_str(&laid_out_text);
+        self.layout.append(core::slice::from_ref(self.font), &TextStyle::new(&laid_out_text, self.size, 0));
+        self.update_glyphs(0);
+    }
+
+    pub fn missing_glyphs(&self) -> &[char] {
+        &self.missing_glyph_chars
+    }
+
     pub fn rect_inner(&self) -> Result<Rect, Error> {
-        let width = self.layout.lines()
-            .and_then(|lines| lines.iter().map(|line| r32(self.inner_bounds.width() - line.padding)).max())
-            .unwrap_or_default()
-            .raw();
-        let height = self.layout.height();
-        Rect::from_xywh(
-            self.inner_bounds.x() + match self.halign {
-                HorizontalAlign::Left => 0.0,
-                HorizontalAlign::Center => (self.inner_bounds.width() - width) / 2.0,
-                HorizontalAlign::Right => self.inner_bounds.width() - width,
-            },
-            self.inner_bounds.y() + match self.valign {
-                VerticalAlign::Top => 0.0,
-                VerticalAlign::Middle => (self.inner_bounds.height() - height) / 2.0,
-                VerticalAlign::Bottom => self.inner_bounds.height() - height,
-            },
-            width,
-            height,
-        ).ok_or(Error::Rect)
+        let width = self.lines.iter()
+            .map(|line| self.inner_bounds.width() - line.padding)
+            .max_by(f32::total_cmp)
+            .unwrap_or_default();
+        let height = self.height;
+        let x = self.inner_bounds.x() + match self.halign {
+            HorizontalAlign::Left => 0.0,
+            HorizontalAlign::Center => (self.inner_bounds.width() - width) / 2.0,
+            HorizontalAlign::Right => self.inner_bounds.width() - width,
+        };
+        let y = self.inner_bounds.y() + match self.valign {
+            VerticalAlign::Top => 0.0,
+            VerticalAlign::Middle => (self.inner_bounds.height() - height) / 2.0,
+            VerticalAlign::Bottom => self.inner_bounds.height() - height,
+            VerticalAlign::Baseline(_) | VerticalAlign::MiddleCapHeight | VerticalAlign::MiddleXHeight => self.y_offset,
+        };
+        Rect::from_xywh(x, y, width, height).and_then(|rect| self.pixel_rounding.rect(rect)).ok_or(Error::Rect { x, y, width, height })
     }
 
-    #[must_use]
     pub fn rect_outer(&self) -> Result<Rect, Error> {
-        self.rect_inner()?.outset(self.size / 2.0, self.size / 2.0).ok_or(Error::Outset)
+        let rect = self.rect_inner()?;
+        rect.outset(self.size / 2.0, self.size / 2.0).and_then(|outer| self.pixel_rounding.rect(outer)).ok_or(Error::Outset { rect, amount: self.size / 2.0 })
+    }
+
+    pub fn draw_rgba(&self, width: u32, height: u32, glyph_cache: &mut GlyphCache) -> Result<Vec<u8>, Error> {
+        let mut canvas = Pixmap::new(width, height).ok_or(Error::Canvas { width, height })?;
+        self.draw(canvas.as_mut(), glyph_cache)?;
+        Ok(canvas.pixels().iter().flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        }).collect())
+    }
+
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity;
+    }
+
+    pub fn metrics(&self) -> Option<Metrics> {
+        Metrics::new(self.font, self.size)
+    }
+
+    #[cfg(feature = "rayon")]
+    fn prerasterize(&self, glyph_cache: &mut GlyphCache) -> Result<(), Error> {
+        use rayon::prelude::*;
+
+        let missing = self.glyphs.iter().enumerate()
+            .filter(|(_, glyph)| glyph.width > 0 && glyph.height > 0 && self.outlines.is_none_or(|(_, threshold)| glyph.key.px < threshold))
+            .flat_map(|(idx, glyph)| self.glyph_layers(idx).map(move |(key, _)| (glyph, key)))
+            .filter(|(_, key)| !glyph_cache.glyphs.contains_key(key))
+            .unique_by(|&(_, key)| key)
+            .collect_vec();
+        let font = self.font;
+        let rasterized = missing.into_par_iter()
+            .map(|(glyph, key)| Ok((key, rasterize(font, glyph.font_index, [glyph.width, glyph.height], key)?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        for (key, glyph) in rasterized {
+            glyph_cache.misses += 1;
+            glyph_cache.insert(key, glyph);
+        }
+        Ok(())
+    }
+
+    fn update_glyph_colors(&mut self) {
+        self.glyph_colors.clear();
+        if self.color_spans.is_empty() { return }
+        let offsets = self.glyph_byte_offsets();
+        let mut order = (0..offsets.len()).collect_vec();
+        order.sort_by_key(|&idx| offsets[idx]);
+        // span boundaries sorted by byte offset, with starts before ends so empty spans never become active
+        let mut events = self.color_spans.iter().enumerate()
+            .flat_map(|(span_idx, (range, _))| [(range.start, false, span_idx), (range.end, true, span_idx)])
+            .collect_vec();
+        events.sort_unstable();
+        let mut events = events.into_iter().peekable();
+        let mut active = BTreeSet::default();
+        self.glyph_colors = vec![self.color; offsets.len()];
+        for idx in order {
+            while let Some((_, is_end, span_idx)) = events.next_if(|&(pos, _, _)| pos <= offsets[idx]) {
+                if is_end { active.remove(&span_idx); } else { active.insert(span_idx); }
+            }
+            // later spans take precedence
+            if let Some(&span_idx) = active.last() {
+                self.glyph_colors[idx] = self.color_spans[span_idx].1;
+            }
+        }
+    }
+
+    /// The color of the glyph at the given index, taking color spans into account.
+    fn glyph_color(&self, idx: usize) -> ColorU8 {
+        self.glyph_colors.get(idx).copied().unwrap_or(self.color)
+    }
+
+    fn glyph_key(&self, idx: usize) -> GlyphKey {
+        let glyph = &self.glyphs[idx];
+        let color = self.glyph_color(idx);
+        GlyphKey {
+            config: glyph.key,
+            color: [color.red(), color.green(), color.blue(), color.alpha()],
+            hinting: self.hinting,
+            antialiasing: self.antialiasing,
+            stroke: 0,
+            scale_x: self.scale_x.to_bits(),
+        }
+    }
+
+    /// The cache keys to draw for a glyph, bottom to top, each with how far its pixmap extends past the glyph's bounds.
+    fn glyph_layers(&self, idx: usize) -> impl Iterator<Item = (GlyphKey, f32)> {
+        let fill = self.glyph_key(idx);
+        self.stroke
+            .map(|(color, width)| (GlyphKey {
+                color: [color.red(), color.green(), color.blue(), color.alpha()],
+                stroke: width.to_bits(),
+                ..fill
+            }, width.ceil()))
+            .into_iter()
+            .chain([(fill, 0.0)])
     }
 
-    pub fn draw(&self, mut canvas: PixmapMut<'_>, glyph_cache: &mut HashMap<(GlyphRasterConfig, [u8; 4]), Pixmap>) -> Result<(), Error> {
-        for glyph in self.layout.glyphs() {
+    fn cached_glyph<'c>(&self, glyph: &GlyphPosition, key: GlyphKey, glyph_cache: &'c mut GlyphCache) -> Result<&'c Pixmap, Error> {
+        if glyph_cache.glyphs.contains_key(&key) {
+            glyph_cache.hits += 1;
+            Ok(&glyph_cache.glyphs[&key])
+        } else {
+            glyph_cache.misses += 1;
+            Ok(glyph_cache.insert(key, rasterize(self.font, glyph.font_index, [glyph.width, glyph.height], key)?))
+        }
+    }
+
+    /// The horizontal extent of a glyph from its origin to its advance.
+    fn glyph_extent(&self, glyph: &GlyphPosition) -> (f32, f32) {
+        let metrics = self.font.metrics_indexed(glyph.key.glyph_index, glyph.key.px);
+        let origin = glyph.x - metrics.xmin as f32 * self.scale_x;
+        (origin, origin + metrics.advance_width * self.scale_x)
+    }
+
+    /// The scale factor at which the widest line, including tracking, exactly fills the bounds width.
+    fn fill_scale(&self) -> f32 {
+        self.lines.iter()
+            .filter_map(|line| line_span(self.font, self.glyphs.get(line.glyph_start..=line.glyph_end)?))
+            .filter(|&(_, width, _)| width > 0.0)
+            .map(|(_, width, gaps)| (self.inner_bounds.width() - self.tracking * gaps as f32) / width)
+            .reduce(f32::min)
+            .unwrap_or(1.0)
+    }
+
+    /// The area covered by a glyph, including its stroke.
+    fn glyph_rect(&self, glyph: &GlyphPosition) -> Option<Rect> {
+        let padding = self.stroke.map_or(0.0, |(_, width)| width.ceil());
+        Rect::from_xywh(glyph.x - padding, glyph.y - padding, glyph.width as f32 + 2.0 * padding, glyph.height as f32 + 2.0 * padding)
+    }
+
+    fn paint(&self, color: ColorU8) -> Paint<'static> {
+        let mut paint = Paint::default();
+        paint.set_color_rgba8(color.red(), color.green(), color.blue(), (f32::from(color.alpha()) * self.opacity.clamp(0.0, 1.0)).round() as u8);
+        paint.anti_alias = self.antialiasing != Antialiasing::None;
+        paint.blend_mode = self.blend_mode;
+        paint
+    }
+
+    pub fn draw(&self, canvas: PixmapMut<'_>, glyph_cache: &mut GlyphCache) -> Result<Option<Rect>, Error> {
+        self.draw_clipped(canvas, glyph_cache, Transform::identity(), None, None)
+    }
+
+    /// Draws the text box moved by `transform`, skipping glyphs entirely outside `clip` and masking all drawing with `mask`.
+    ///
+    /// `transform` must be a translation. `clip` and the returned rect are in canvas coordinates.
+    fn draw_clipped(&self, mut canvas: PixmapMut<'_>, glyph_cache: &mut GlyphCache, transform: Transform, clip: Option<Rect>, mask: Option<&Mask>) -> Result<Option<Rect>, Error> {
+        if let Some((c, byte_offset)) = self.control_char {
+            return Err(Error::ControlChar { c, byte_offset })
+        }
+        #[cfg(feature = "rayon")] self.prerasterize(glyph_cache)?;
+        let outlines = self.outlines
+            .map(|(font_data, threshold)| Ok::<_, Error>((ttf_parser::Face::parse(font_data, 0)?, threshold)))
+            .transpose()?;
+        let pixmap_paint = PixmapPaint {
+            opacity: self.opacity.clamp(0.0, 1.0),
+            blend_mode: self.blend_mode,
+            ..PixmapPaint::default()
+        };
+        let mut dirty = self.draw_background(&mut canvas, transform, mask)?;
+        dirty = union(dirty, self.draw_stripes(&mut canvas, transform, mask));
+        let fade_mask = self.fade_mask(canvas.width(), canvas.height(), transform, mask)?;
+        let mask = fade_mask.as_ref().or(mask);
+        dirty = union(dirty, self.draw_glow(&mut canvas, glyph_cache, transform, mask)?);
+        dirty = union(dirty, self.draw_decorations(&mut canvas, transform, mask, |line| line != DecorationLine::Strikethrough));
+        for (idx, glyph) in self.glyphs.iter().enumerate() {
             if glyph.width > 0 && glyph.height > 0 {
-                match glyph_cache.entry((glyph.key, [self.color.red(), self.color.green(), self.color.blue(), self.color.alpha()])) {
-                    hash_map::Entry::Occupied(entry) => canvas.draw_pixmap(0, 0, entry.get().as_ref(), &PixmapPaint::default(), Transform::from_translate(glyph.x, glyph.y), None),
-                    hash_map::Entry::Vacant(entry) => {
-                        let (_, data) = self.font.rasterize_config(glyph.key);
-                        let mut glyph_canvas = Pixmap::new(glyph.width as u32, glyph.height as u32).ok_or(Error::GlyphPixmap)?;
-                        for (alpha, pixel) in data.into_iter().zip_eq(glyph_canvas.pixels_mut()) {
-                            *pixel = ColorU8::from_rgba(self.color.red(), self.color.green(), self.color.blue(), (u16::from(self.color.alpha()) * u16::from(alpha) / 255) as u8).premultiply();
-                        }
-                        canvas.draw_pixmap(0, 0, glyph_canvas.as_ref(), &PixmapPaint::default(), Transform::from_translate(glyph.x, glyph.y), None);
-                        entry.insert(glyph_canvas);
+                let glyph_rect = self.glyph_rect(glyph).and_then(|glyph_rect| glyph_rect.transform(transform));
+                if clip.is_some_and(|clip| glyph_rect.is_none_or(|glyph_rect| glyph_rect.intersect(&clip).is_none())) { continue }
+                if let Some((face, _)) = outlines.as_ref().filter(|&&(_, threshold)| glyph.key.px >= threshold) {
+                    let Some((path, outline_transform, scale)) = self.glyph_outline(face, glyph) else { continue };
+                    let outline_transform = transform.pre_concat(outline_transform);
+                    if let Some((color, width)) = self.stroke {
+                        let stroke = Stroke {
+                            width: 2.0 * width / scale,
+                            line_join: LineJoin::Round,
+                            ..Stroke::default()
+                        };
+                        canvas.stroke_path(&path, &self.paint(color), &stroke, outline_transform, mask);
+                    }
+                    if self.pattern.is_none() {
+                        canvas.fill_path(&path, &self.paint(self.glyph_color(idx)), FillRule::Winding, outline_transform, mask);
+                    }
+                } else {
+                    for (key, padding) in self.glyph_layers(idx).filter(|(key, _)| self.pattern.is_none() || key.stroke != 0) {
+                        let glyph_canvas = self.cached_glyph(glyph, key, glyph_cache)?;
+                        canvas.draw_pixmap(0, 0, glyph_canvas.as_ref(), &pixmap_paint, transform.pre_translate(glyph.x - padding, glyph.y - padding), mask);
                     }
                 }
+                dirty = union(dirty, glyph_rect);
             }
         }
-        Ok(())
+        self.draw_pattern(&mut canvas, glyph_cache, transform, mask)?;
+        self.draw_emboss(&mut canvas, glyph_cache, transform, mask)?;
+        dirty = union(dirty, self.draw_decorations(&mut canvas, transform, mask, |line| line == DecorationLine::Strikethrough));
+        Ok(dirty.and_then(|dirty| dirty.intersect(&Rect::from_xywh(0.0, 0.0, canvas.width() as f32, canvas.height() as f32)?)))
     }
 }
 
+fn rasterize(font: &Font, font_index: usize, [width, height]: [usize; 2], key: GlyphKey) -> Result<Pixmap, Error> {
+    let check_size = |expected: usize, actual: usize| if actual == expected { Ok(()) } else {
+        Err(Error::CoverageSize {
+            glyph_index: key.config.glyph_index,
+            font_index,
+            expected,
+            actual,
+        })
+    };
+    let (metrics, mut data) = font.rasterize_config(key.config);
+    check_size(metrics.width * metrics.height, data.len())?;
+    let scale_x = f32::from_bits(key.scale_x);
+    check_size(width * height, scaled_width(metrics.width, scale_x) * metrics.height)?;
+    match key.antialiasing {
+        Antialiasing::None => for alpha in &mut data {
+            *alpha = if *alpha >= 128 { 255 } else { 0 };
+        },
+        Antialiasing::Grayscale => {}
+        Antialiasing::Supersampled => {
+            data = supersample(font, key.config, metrics);
+            check_size(metrics.width * metrics.height, data.len())?;
+        }
+    }
+    if key.hinting == Hinting::Full {
+        let gamma = (key.config.px / 48.0).clamp(0.5, 1.0);
+        for alpha in &mut data {
+            *alpha = (255.0 * (f32::from(*alpha) / 255.0).powf(gamma)).round() as u8;
+        }
+    }
+    if scale_x != 1.0 {
+        data = resample_horizontally(&data, [metrics.width, height], width);
+    }
+    let [width, height] = if key.stroke == 0 {
+        [width, height]
+    } else {
+        let radius = f32::from_bits(key.stroke);
+        let padding = radius.ceil() as usize;
+        data = dilate(&data, [width, height], radius, padding);
+        [width + 2 * padding, height + 2 * padding]
+    };
+    // a buffer of the wrong length would otherwise leave pixels unset or drop coverage when zipped with the pixmap
+    check_size(width * height, data.len())?;
+    let [red, green, blue, alpha] = key.color;
+    let color = ColorU8::from_rgba(red, green, blue, alpha);
+    let mut glyph_canvas = Pixmap::new(width as u32, height as u32).ok_or(Error::GlyphPixmap {
+        glyph_index: key.config.glyph_index,
+        font_index,
+        width,
+        height,
+    })?;
+    #[cfg(feature = "simd")] {
+        let palette = core::array::from_fn::<_, 256, _>(|alpha| tint(color, alpha as u8));
+        let mut data_chunks = data.chunks_exact(8);
+        let mut pixel_chunks = glyph_canvas.pixels_mut().chunks_exact_mut(8);
+        for (alphas, pixels) in (&mut data_chunks).zip(&mut pixel_chunks) {
+            for (&alpha, pixel) in alphas.iter().zip(pixels) {
+                *pixel = palette[usize::from(alpha)];
+            }
+        }
+        for (&alpha, pixel) in data_chunks.remainder().iter().zip(pixel_chunks.into_remainder()) {
+            *pixel = palette[usize::from(alpha)];
+        }
+    }
+    #[cfg(not(feature = "simd"))] {
+        for (alpha, pixel) in data.into_iter().zip(glyph_canvas.pixels_mut()) {
+            *pixel = tint(color, alpha);
+        }
+    }
+    Ok(glyph_canvas)
+}
+
+fn supersample(font: &Font, config: GlyphRasterConfig, metrics: fontdue::Metrics) -> Vec<u8> {
+    const FACTOR: i32 = 4;
+
+    let (hires_metrics, hires_data) = font.rasterize_indexed(config.glyph_index, config.px * FACTOR as f32);
+    let hires_top = hires_metrics.ymin + hires_metrics.height as i32;
+    let mut data = Vec::with_capacity(metrics.width * metrics.height);
+    for y in 0..metrics.height as i32 {
+        for x in 0..metrics.width as i32 {
+            let hires_x = FACTOR * (metrics.xmin + x) - hires_metrics.xmin;
+            let hires_y = hires_top - FACTOR * (metrics.ymin + metrics.height as i32 - y);
+            let mut sum = 0;
+            for sample_y in hires_y..hires_y + FACTOR {
+                for sample_x in hires_x..hires_x + FACTOR {
+                    if (0..hires_metrics.width as i32).contains(&sample_x) && (0..hires_metrics.height as i32).contains(&sample_y) {
+                        sum += u32::from(hires_data[sample_y as usize * hires_metrics.width + sample_x as usize]);
+                    }
+                }
+            }
+            data.push((sum / (FACTOR * FACTOR) as u32) as u8);
+        }
+    }
+    data
+}
+
+/// Linearly resamples each row of coverage to a new width, for horizontal glyph scaling.
+fn resample_horizontally(data: &[u8], [width, height]: [usize; 2], new_width: usize) -> Vec<u8> {
+    let scale = width as f32 / new_width as f32;
+    let mut out = Vec::with_capacity(new_width * height);
+    for row in data.chunks_exact(width) {
+        out.extend((0..new_width).map(|x| {
+            let source_x = ((x as f32 + 0.5) * scale - 0.5).clamp(0.0, (width - 1) as f32);
+            let (left, fraction) = (source_x.floor() as usize, source_x.fract());
+            let right = (left + 1).min(width - 1);
+            (f32::from(row[left]) * (1.0 - fraction) + f32::from(row[right]) * fraction).round() as u8
+        }));
+    }
+    out
+}
+
+/// Spreads coverage outward by `radius` pixels, for strokes.
+fn dilate(data: &[u8], [width, height]: [usize; 2], radius: f32, padding: usize) -> Vec<u8> {
+    let reach = padding as isize + 1;
+    let out_width = width + 2 * padding;
+    let mut out = vec![0; out_width * (height + 2 * padding)];
+    for (idx, value) in out.iter_mut().enumerate() {
+        let (x, y) = ((idx % out_width) as isize - padding as isize, (idx / out_width) as isize - padding as isize);
+        let mut max = 0.0f32;
+        for (dx, dy) in (-reach..=reach).cartesian_product(-reach..=reach) {
+            let (source_x, source_y) = (x + dx, y + dy);
+            if source_x < 0 || source_y < 0 || source_x >= width as isize || source_y >= height as isize { continue }
+            let weight = (radius + 0.5 - ((dx * dx + dy * dy) as f32).sqrt()).clamp(0.0, 1.0);
+            max = max.max(f32::from(data[source_y as usize * width + source_x as usize]) * weight);
+        }
+        *value = max.round() as u8;
+    }
+    out
+}
+
+impl TextBox<'_, '_> {
+    /// The outline of a glyph with the transform that places it, and the scale from font units to pixels.
+    fn glyph_outline(&self, face: &ttf_parser::Face<'_>, glyph: &GlyphPosition) -> Option<(Path, Transform, f32)> {
+        struct OutlineBuilder(PathBuilder);
+
+        impl ttf_parser::OutlineBuilder for OutlineBuilder {
+            fn move_to(&mut self, x: f32, y: f32) { self.0.move_to(x, y) }
+            fn line_to(&mut self, x: f32, y: f32) { self.0.line_to(x, y) }
+            fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) { self.0.quad_to(x1, y1, x, y) }
+            fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) { self.0.cubic_to(x1, y1, x2, y2, x, y) }
+            fn close(&mut self) { self.0.close() }
+        }
+
+        let mut builder = OutlineBuilder(PathBuilder::new());
+        face.outline_glyph(ttf_parser::GlyphId(glyph.key.glyph_index), &mut builder)?;
+        let path = builder.0.finish()?;
+        let metrics = self.font.metrics_indexed(glyph.key.glyph_index, glyph.key.px);
+        let scale = self.font.scale_factor(glyph.key.px);
+        let origin_x = glyph.x - metrics.xmin as f32 * self.scale_x;
+        let baseline_y = glyph.y + metrics.height as f32 + metrics.ymin as f32;
+        Some((path, Transform::from_row(scale * self.scale_x, 0.0, 0.0, -scale, origin_x, baseline_y), scale))
+    }
+}
+
+fn tint(color: ColorU8, alpha: u8) -> PremultipliedColorU8 {
+    ColorU8::from_rgba(color.red(), color.green(), color.blue(), (u16::from(color.alpha()) * u16::from(alpha) / 255) as u8).premultiply()
+}
+
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum Error {
-    #[error("failed to create glyph canvas")]
-    GlyphPixmap,
-    #[error("failed to inset text rect")]
-    Inset,
-    #[error("failed to outset text rect")]
-    Outset,
-    #[error("failed to calculate text dimensions")]
-    Rect,
+    #[error("the {width}×{height} glyph atlas is full")]
+    AtlasFull {
+        width: u32,
+        height: u32,
+    },
+    #[error("invalid glyph cache file")]
+    CacheFormat,
+    #[error("failed to create {width}×{height} canvas")]
+    Canvas {
+        width: u32,
+        height: u32,
+    },
+    #[error("invalid color: {0:?}")]
+    Color(String),
+    #[error("control character {c:?} at byte offset {byte_offset}")]
+    ControlChar {
+        c: char,
+        byte_offset: usize,
+    },
+    #[error("failed to load font: {0}")]
+    Font(&'static str),
+    #[error("a text box snapshot was drawn with a different font than it was laid out with")]
+    FontMismatch,
+    #[error("rasterizer returned {actual} coverage values for glyph {glyph_index} of font {font_index}, expected {expected}")]
+    CoverageSize {
+        glyph_index: u16,
+        font_index: usize,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("failed to create {width}×{height} canvas for glyph {glyph_index} of font {font_index}")]
+    GlyphPixmap {
+        glyph_index: u16,
+        font_index: usize,
+        width: usize,
+        height: usize,
+    },
+    #[error("failed to inset text rect {bounds:?} by {amount}")]
+    Inset {
+        bounds: Rect,
+        amount: f32,
+    },
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("failed to outset text rect {rect:?} by {amount}")]
+    Outset {
+        rect: Rect,
+        amount: f32,
+    },
+    #[error("failed to parse font outlines")]
+    Outlines(#[cfg_attr(feature = "std", from)] ttf_parser::FaceParsingError),
+    #[error("failed to calculate text dimensions: x = {x}, y = {y}, width = {width}, height = {height}")]
+    Rect {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+    #[error("unknown style token: {0:?}")]
+    StyleToken(String),
+    #[cfg(feature = "syntect")]
+    #[error(transparent)]
+    Syntect(#[from] syntect::Error),
+    #[error("no style named {0:?} in theme")]
+    UnknownStyle(String),
+}
+
+/// ttf-parser only implements `Error` for its error type with `std`, so it can't be a source otherwise.
+#[cfg(not(feature = "std"))]
+impl From<ttf_parser::FaceParsingError> for Error {
+    fn from(e: ttf_parser::FaceParsingError) -> Self {
+        Self::Outlines(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Normalizes text appended in the given chunks the way a text box does.
+    fn normalize_appended(chunks: &[&str]) -> String {
+        let mut trailing_cr = false;
+        chunks.iter().map(|chunk| normalize_newlines(continue_crlf(&mut trailing_cr, chunk)).into_owned()).collect()
+    }
+
+    #[test]
+    fn normalize_line_breaks() {
+        assert!(matches!(normalize_newlines("a\nb"), Cow::Borrowed("a\nb")));
+        assert_eq!(normalize_newlines("a\r\nb\rc\u{85}d\u{2028}e\u{2029}f"), "a\nb\nc\nd\ne\nf");
+        assert_eq!(normalize_newlines("a\r\r\nb"), "a\n\nb");
+    }
+
+    #[test]
+    fn normalize_crlf_split_across_appends() {
+        assert_eq!(normalize_appended(&["a\r", "\nb"]), "a\nb");
+        assert_eq!(normalize_appended(&["a\r", "", "\nb"]), "a\nb");
+        assert_eq!(normalize_appended(&["a\r", "b\n"]), "a\nb\n");
+        assert_eq!(normalize_appended(&["a\n", "\nb"]), "a\n\nb");
+    }
+
+    #[test]
+    fn control_char_policies() {
+        let text = "a\u{7}b\u{200b}c\td\ne\u{7f}";
+        assert!(matches!(handle_control_chars(ControlChars::Keep, text), Ok(Cow::Borrowed(kept)) if kept == text));
+        assert_eq!(handle_control_chars(ControlChars::Strip, text).unwrap(), "abc\td\ne");
+        assert_eq!(handle_control_chars(ControlChars::Escape, text).unwrap(), "a\u{2407}b<U+200B>c\td\ne\u{2421}");
+        assert_eq!(handle_control_chars(ControlChars::Error, text), Err(('\u{7}', 1)));
+    }
+
+    #[test]
+    fn control_chars_allow_tabs_and_newlines() {
+        for policy in [ControlChars::Keep, ControlChars::Strip, ControlChars::Escape, ControlChars::Error] {
+            assert!(matches!(handle_control_chars(policy, "a\tb\nc"), Ok(Cow::Borrowed("a\tb\nc"))));
+        }
+    }
+
+    #[test]
+    fn parse_hex_colors() {
+        assert_eq!("#f80".try_into_color_u8().unwrap(), ColorU8::from_rgba(0xff, 0x88, 0x00, 0xff));
+        assert_eq!("#f808".try_into_color_u8().unwrap(), ColorU8::from_rgba(0xff, 0x88, 0x00, 0x88));
+        assert_eq!("#1a2B3c".try_into_color_u8().unwrap(), ColorU8::from_rgba(0x1a, 0x2b, 0x3c, 0xff));
+        assert_eq!("1a2b3c4d".try_into_color_u8().unwrap(), ColorU8::from_rgba(0x1a, 0x2b, 0x3c, 0x4d));
+    }
+
+    #[test]
+    fn reject_invalid_hex_colors() {
+        for color in ["", "#", "#12", "#12345", "#1234567", "#123456789", "#ggg", "#ff\u{e9}"] {
+            assert!(matches!(color.try_into_color_u8(), Err(Error::Color(invalid)) if invalid == color), "{color:?}");
+        }
+    }
+
+    #[test]
+    fn convert_packed_colors() {
+        assert_eq!(0x1a2b3c4d.into_color_u8(), ColorU8::from_rgba(0x1a, 0x2b, 0x3c, 0x4d));
+        assert_eq!([1, 2, 3].into_color_u8(), ColorU8::from_rgba(1, 2, 3, 0xff));
+        assert_eq!([1, 2, 3, 4].into_color_u8(), ColorU8::from_rgba(1, 2, 3, 4));
+    }
 }
@@ -0,0 +1,198 @@
+use {
+    alloc::{
+        string::String,
+        vec::Vec,
+    },
+    fontdue::layout::{
+        GlyphPosition,
+        Layout,
+        LayoutSettings,
+        TextStyle,
+    },
+    hashbrown::hash_map,
+    itertools::Itertools as _,
+    unicode_normalization::UnicodeNormalization as _,
+    crate::{
+        Bounds,
+        Builder,
+        ControlChars,
+        LayoutCache,
+        MissingGlyphs,
+        handle_control_chars,
+        normalize_newlines,
+        substitute_missing_glyphs,
+    },
+};
+
+#[derive(PartialEq, Eq, Hash)]
+pub(crate) struct RunKey {
+    size: u32,
+    font_hash: usize,
+    nfc: bool,
+    control_chars: ControlChars,
+    missing_glyphs: MissingGlyphs,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct RunMeasurement {
+    intrinsic_size: [f32; 2],
+    min_content_width: f32,
+}
+
+/// A run of text after the same preprocessing that layout applies, shared by measuring and building the same text.
+pub(crate) struct Run {
+    pub(crate) text: String,
+    pub(crate) missing_glyphs: Vec<char>,
+    pub(crate) control_char: Option<(char, usize)>,
+    /// Filled in the first time the run is measured, since building doesn't lay it out without a width limit.
+    measurement: Option<RunMeasurement>,
+}
+
+impl LayoutCache {
+    /// Looks up the preprocessed run of `text`, or preprocesses it and stores the result.
+    pub(crate) fn run(&mut self, text: &str, key: RunKey, prepare: impl FnOnce() -> Run) -> &mut Run {
+        match self.runs.entry_ref(text).or_default().entry(key) {
+            hash_map::Entry::Occupied(entry) => {
+                self.hits += 1;
+                entry.into_mut()
+            }
+            hash_map::Entry::Vacant(entry) => {
+                self.misses += 1;
+                entry.insert(prepare())
+            }
+        }
+    }
+}
+
+impl<B: Bounds> Builder<'_, '_, B> {
+    /// The text after the same preprocessing that layout applies.
+    pub(crate) fn prepared_text(&self) -> String {
+        self.prepare_run().text
+    }
+
+    pub(crate) fn prepare_run(&self) -> Run {
+        let text = normalize_newlines(self.text);
+        let text = if self.nfc { text.nfc().collect() } else { text.into_owned() };
+        let (text, control_char) = match handle_control_chars(self.control_chars, &text) {
+            Ok(handled) => (handled.into_owned(), None),
+            Err(control_char) => (text, Some(control_char)),
+        };
+        let mut missing_glyphs = Vec::default();
+        let text = substitute_missing_glyphs(self.font, self.missing_glyphs, &text, |c| if !missing_glyphs.contains(&c) {
+            missing_glyphs.push(c);
+        }).into_owned();
+        Run { text, missing_glyphs, control_char, measurement: None }
+    }
+
+    pub(crate) fn run_key(&self) -> RunKey {
+        RunKey {
+            size: self.size_px().to_bits(),
+            font_hash: self.font.file_hash(),
+            nfc: self.nfc,
+            control_chars: self.control_chars,
+            missing_glyphs: self.missing_glyphs,
+        }
+    }
+
+    fn measure(&self, layout: &mut Layout, max_width: Option<f32>) -> [f32; 2] {
+        self.measure_prepared(layout, &self.prepared_text(), max_width)
+    }
+
+    fn measure_prepared(&self, layout: &mut Layout, text: &str, max_width: Option<f32>) -> [f32; 2] {
+        layout.reset(&LayoutSettings {
+            max_width,
+            ..LayoutSettings::default()
+        });
+        layout.append(core::slice::from_ref(self.font), &TextStyle::new(text, self.size_px(), 0));
+        let width = layout.glyphs().iter()
+            .filter(|glyph| !glyph.parent.is_whitespace())
+            .map(|glyph| self.advance_extent(glyph).1)
+            .fold(0.0, f32::max);
+        [width, layout.height()]
+    }
+
+    /// The horizontal range covered by a laid out glyph's advance.
+    fn advance_extent(&self, glyph: &GlyphPosition) -> (f32, f32) {
+        let metrics = self.font.metrics_indexed(glyph.key.glyph_index, glyph.key.px);
+        let left = glyph.x - metrics.xmin as f32;
+        (left, left + metrics.advance_width)
+    }
+
+    /// The size of the text laid out without a width or height limit, so that lines only break at hard line breaks. Useful for sizing a container to its content.
+    pub fn intrinsic_size(&self, layout: &mut Layout) -> [f32; 2] {
+        self.measure(layout, None)
+    }
+
+    /// The width of the widest unbreakable run of text, i.e. the narrowest the text can be laid out without overflowing.
+    pub fn min_content_width(&self, layout: &mut Layout) -> f32 {
+        self.measure(layout, None);
+        self.min_content_width_of(layout)
+    }
+
+    /// The widest unbreakable run of text in a layout without a width limit.
+    fn min_content_width_of(&self, layout: &Layout) -> f32 {
+        layout.glyphs().iter()
+            .chunk_by(|glyph| !glyph.parent.is_whitespace())
+            .into_iter()
+            .filter(|(is_word, _)| *is_word)
+            .filter_map(|(_, word)| word.map(|glyph| self.advance_extent(glyph)).reduce(|(left1, right1), (left2, right2)| (left1.min(left2), right1.max(right2))))
+            .map(|(left, right)| right - left)
+            .fold(0.0, f32::max)
+    }
+
+    /// The width of the text without any soft wrapping.
+    pub fn max_content_width(&self, layout: &mut Layout) -> f32 {
+        self.measure(layout, None)[0]
+    }
+
+    /// Measures the text without a width limit, or looks up an earlier measurement of the same text with the same font, size, and preprocessing.
+    /// The preprocessed run is shared with [`build_cached`](Self::build_cached), so measuring and then building the same text only preprocesses it once.
+    fn measure_run_cached(&self, layout: &mut Layout, cache: &mut LayoutCache) -> RunMeasurement {
+        let run = cache.run(self.text, self.run_key(), || self.prepare_run());
+        if let Some(measurement) = run.measurement { return measurement }
+        let intrinsic_size = self.measure_prepared(layout, &run.text, None);
+        let measurement = RunMeasurement { intrinsic_size, min_content_width: self.min_content_width_of(layout) };
+        run.measurement = Some(measurement);
+        measurement
+    }
+
+    /// Like [`intrinsic_size`](Self::intrinsic_size), but reuses measurements of the same text stored in the cache, e.g. by bots re-rendering the same phrases.
+    pub fn intrinsic_size_cached(&self, layout: &mut Layout, cache: &mut LayoutCache) -> [f32; 2] {
+        self.measure_run_cached(layout, cache).intrinsic_size
+    }
+
+    /// Like [`min_content_width`](Self::min_content_width), but reuses measurements of the same text stored in the cache.
+    pub fn min_content_width_cached(&self, layout: &mut Layout, cache: &mut LayoutCache) -> f32 {
+        self.measure_run_cached(layout, cache).min_content_width
+    }
+
+    /// Like [`max_content_width`](Self::max_content_width), but reuses measurements of the same text stored in the cache.
+    pub fn max_content_width_cached(&self, layout: &mut Layout, cache: &mut LayoutCache) -> f32 {
+        self.measure_run_cached(layout, cache).intrinsic_size[0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key() -> RunKey {
+        RunKey { size: 16.0f32.to_bits(), font_hash: 0, nfc: false, control_chars: ControlChars::Keep, missing_glyphs: MissingGlyphs::Tofu }
+    }
+
+    fn run(text: &str) -> Run {
+        Run { text: String::from(text), missing_glyphs: Vec::default(), control_char: None, measurement: None }
+    }
+
+    #[test]
+    fn measure_then_build_counts_single_miss() {
+        let mut cache = LayoutCache::new();
+        // measuring stores the measurement in the run…
+        cache.run("hello", key(), || run("hello")).measurement = Some(RunMeasurement { intrinsic_size: [40.0, 16.0], min_content_width: 40.0 });
+        // …and building the same text reuses the run instead of preprocessing it again
+        let built = cache.run("hello", key(), || panic!("run preprocessed twice"));
+        assert!(built.measurement.is_some());
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+    }
+}
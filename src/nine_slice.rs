@@ -0,0 +1,70 @@
+use {
+    tiny_skia::{
+        FilterQuality,
+        Mask,
+        Paint,
+        Pattern,
+        Pixmap,
+        PixmapMut,
+        Rect,
+        SpreadMode,
+        Transform,
+    },
+    crate::{
+        Error,
+        TextBox,
+    },
+};
+
+/// A background image whose corners are drawn unscaled, edges stretched in one direction, and center stretched in both.
+#[derive(Debug, Clone, Copy)]
+pub struct NineSlice<'a> {
+    pub pixmap: &'a Pixmap,
+    /// Widths of the left, top, right, and bottom borders in source pixels.
+    pub insets: [u32; 4],
+}
+
+impl NineSlice<'_> {
+    pub fn draw(&self, canvas: &mut PixmapMut<'_>, rect: Rect, opacity: f32, mask: Option<&Mask>) {
+        self.draw_transformed(canvas, rect, opacity, Transform::identity(), mask);
+    }
+
+    fn draw_transformed(&self, canvas: &mut PixmapMut<'_>, rect: Rect, opacity: f32, transform: Transform, mask: Option<&Mask>) {
+        let [left, top, right, bottom] = self.insets.map(|inset| inset as f32);
+        let (width, height) = (self.pixmap.width() as f32, self.pixmap.height() as f32);
+        // if the destination is smaller than the borders, shrink the borders proportionally
+        let scale_x = (rect.width() / (left + right)).min(1.0);
+        let scale_y = (rect.height() / (top + bottom)).min(1.0);
+        let source_x = [0.0, left, width - right, width];
+        let source_y = [0.0, top, height - bottom, height];
+        let dest_x = [rect.left(), rect.left() + left * scale_x, rect.right() - right * scale_x, rect.right()];
+        let dest_y = [rect.top(), rect.top() + top * scale_y, rect.bottom() - bottom * scale_y, rect.bottom()];
+        for row in 0..3 {
+            for column in 0..3 {
+                let (source_width, source_height) = (source_x[column + 1] - source_x[column], source_y[row + 1] - source_y[row]);
+                if source_width <= 0.0 || source_height <= 0.0 { continue }
+                let Some(dest) = Rect::from_ltrb(dest_x[column], dest_y[row], dest_x[column + 1], dest_y[row + 1]) else { continue };
+                let scale = (dest.width() / source_width, dest.height() / source_height);
+                let slice_transform = Transform::from_row(scale.0, 0.0, 0.0, scale.1, dest.left() - source_x[column] * scale.0, dest.top() - source_y[row] * scale.1);
+                let paint = Paint {
+                    // nearest neighbor sampling keeps neighboring slices from bleeding into each other
+                    shader: Pattern::new(self.pixmap.as_ref(), SpreadMode::Pad, FilterQuality::Nearest, opacity.clamp(0.0, 1.0), slice_transform),
+                    ..Paint::default()
+                };
+                canvas.fill_rect(dest, &paint, transform, mask);
+            }
+        }
+    }
+}
+
+impl TextBox<'_, '_> {
+    /// Draws the nine-slice background, if any, around the outer rect and returns the area it covers.
+    pub(crate) fn draw_background(&self, canvas: &mut PixmapMut<'_>, transform: Transform, mask: Option<&Mask>) -> Result<Option<Rect>, Error> {
+        let Some((nine_slice, padding)) = self.background else { return Ok(None) };
+        if self.glyphs.is_empty() { return Ok(None) }
+        let outer = self.rect_outer()?;
+        let rect = outer.outset(padding, padding).ok_or(Error::Outset { rect: outer, amount: padding })?;
+        nine_slice.draw_transformed(canvas, rect, self.opacity, transform, mask);
+        Ok(rect.transform(transform))
+    }
+}
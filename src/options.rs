@@ -0,0 +1,119 @@
+use {
+    fontdue::{
+        Font,
+        layout::Layout,
+    },
+    tiny_skia::{
+        Color,
+        ColorU8,
+        PixmapMut,
+        Rect,
+    },
+    crate::{
+        Antialiasing,
+        Builder,
+        DEFAULT_DPI,
+        DEFAULT_SIZE,
+        Direction,
+        Error,
+        GlyphCache,
+        HAlign,
+        Hinting,
+        PixelRounding,
+        Size,
+        TextBox,
+        VerticalAlign,
+    },
+};
+
+/// Where to lay out text rendered with [`render`], chosen at runtime rather than through the builder's type parameter.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum DynBounds {
+    /// The canvas, inset by half the font size on each side, like [`Builder::build`] for [`DefaultBounds`](crate::DefaultBounds).
+    #[default]
+    Canvas,
+    /// Like [`Builder::bounds_inner`].
+    Inner(Rect),
+    /// Like [`Builder::bounds_outer`].
+    Outer(Rect),
+}
+
+/// Text settings as a plain struct, for callers driven by configuration rather than code. Defaults match those of [`Builder::new`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextOptions {
+    pub bounds: DynBounds,
+    pub size: Size,
+    /// See [`Builder::dpi`].
+    pub dpi: f32,
+    /// See [`Builder::root_size`].
+    pub root_size: f32,
+    pub color: ColorU8,
+    pub halign: HAlign,
+    pub direction: Direction,
+    pub valign: VerticalAlign,
+    pub hanging_punctuation: bool,
+    pub hinting: Hinting,
+    pub antialiasing: Antialiasing,
+    pub pixel_rounding: PixelRounding,
+    pub opacity: f32,
+    pub tracking: Size,
+    /// See [`Builder::scale_factor`].
+    pub scale_factor: f32,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        Self {
+            bounds: DynBounds::default(),
+            size: Size::Px(DEFAULT_SIZE),
+            dpi: DEFAULT_DPI,
+            root_size: DEFAULT_SIZE,
+            color: Color::WHITE.to_color_u8(),
+            halign: HAlign::Center,
+            direction: Direction::default(),
+            valign: VerticalAlign::Middle,
+            hanging_punctuation: false,
+            hinting: Hinting::None,
+            antialiasing: Antialiasing::Grayscale,
+            pixel_rounding: PixelRounding::None,
+            opacity: 1.0,
+            tracking: Size::Px(0.0),
+            scale_factor: 1.0,
+        }
+    }
+}
+
+impl TextOptions {
+    /// Lays out text with these options. `canvas_size` is in physical pixels and only used for [`DynBounds::Canvas`].
+    pub fn build<'f, 'l>(&self, font: &'f Font, text: &str, layout: &'l mut Layout, canvas_size: [f32; 2]) -> Result<TextBox<'f, 'l>, Error> {
+        let builder = Builder::new(font, text)
+            .size(self.size)
+            .dpi(self.dpi)
+            .root_size(self.root_size)
+            .color(self.color)
+            .halign(self.halign)
+            .direction(self.direction)
+            .valign(self.valign)
+            .hanging_punctuation(self.hanging_punctuation)
+            .hinting(self.hinting)
+            .antialiasing(self.antialiasing)
+            .pixel_rounding(self.pixel_rounding)
+            .opacity(self.opacity)
+            .tracking(self.tracking)
+            .scale_factor(self.scale_factor);
+        Ok(match self.bounds {
+            DynBounds::Canvas => builder.build(layout, canvas_size)?,
+            DynBounds::Inner(bounds) => builder.bounds_inner(bounds).build(layout),
+            DynBounds::Outer(bounds) => builder.bounds_outer(bounds).build(layout)?,
+        })
+    }
+}
+
+/// Lays out and draws text in one call, without going through the typestate builder. Returns the area that was drawn to.
+///
+/// The layout is reset before use, so the same one can be passed to every call. When rendering from multiple threads, take one from a shared
+/// [`LayoutPool`](crate::LayoutPool) with `&mut pool.get()`.
+pub fn render(options: &TextOptions, font: &Font, text: &str, layout: &mut Layout, canvas: PixmapMut<'_>, glyph_cache: &mut GlyphCache) -> Result<Option<Rect>, Error> {
+    let text_box = options.build(font, text, layout, [canvas.width() as f32, canvas.height() as f32])?;
+    text_box.draw(canvas, glyph_cache)
+}
@@ -0,0 +1,74 @@
+use {
+    alloc::{
+        string::String,
+        sync::Arc,
+    },
+    fontdue::{
+        Font,
+        layout::{
+            CoordinateSystem,
+            Layout,
+        },
+    },
+    tiny_skia::{
+        PixmapMut,
+        Rect,
+    },
+    crate::{
+        Error,
+        GlyphCache,
+        TextBox,
+        TextOptions,
+    },
+};
+#[cfg(feature = "std")] use crate::{
+    LayoutPool,
+    render,
+};
+
+/// Text along with its font, options, and layout, without any borrows, so it can be kept in long-lived structs and sent across threads.
+///
+/// The text is laid out again each time it's drawn or a [`TextBox`] is requested. When drawing many text boxes in a hot loop or from multiple threads,
+/// [`draw_pooled`](Self::draw_pooled) lays out in a layout borrowed from a shared pool instead of the text box's own.
+pub struct OwnedTextBox {
+    font: Arc<Font>,
+    text: String,
+    pub options: TextOptions,
+    layout: Layout,
+}
+
+impl OwnedTextBox {
+    pub fn new(font: Arc<Font>, text: impl Into<String>, options: TextOptions) -> Self {
+        Self {
+            text: text.into(),
+            layout: Layout::new(CoordinateSystem::PositiveYDown),
+            font, options,
+        }
+    }
+
+    pub fn font(&self) -> &Arc<Font> { &self.font }
+    pub fn text(&self) -> &str { &self.text }
+
+    pub fn set_font(&mut self, font: Arc<Font>) {
+        self.font = font;
+    }
+
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+    }
+
+    /// Lays out the text, e.g. to query its size or regions. `canvas_size` is only used for [`DynBounds::Canvas`](crate::DynBounds::Canvas).
+    pub fn text_box(&mut self, canvas_size: [f32; 2]) -> Result<TextBox<'_, '_>, Error> {
+        self.options.build(&self.font, &self.text, &mut self.layout, canvas_size)
+    }
+
+    pub fn draw(&mut self, canvas: PixmapMut<'_>, glyph_cache: &mut GlyphCache) -> Result<Option<Rect>, Error> {
+        self.text_box([canvas.width() as f32, canvas.height() as f32])?.draw(canvas, glyph_cache)
+    }
+
+    /// Like [`draw`](Self::draw), but lays out in a layout taken from `pool`, so it only needs shared access to the text box.
+    #[cfg(feature = "std")]
+    pub fn draw_pooled(&self, pool: &LayoutPool, canvas: PixmapMut<'_>, glyph_cache: &mut GlyphCache) -> Result<Option<Rect>, Error> {
+        render(&self.options, &self.font, &self.text, &mut pool.get(), canvas, glyph_cache)
+    }
+}
@@ -0,0 +1,159 @@
+use {
+    std::{
+        fs::File,
+        io::{
+            self,
+            BufReader,
+            BufWriter,
+            prelude::*,
+        },
+        path::Path,
+    },
+    fontdue::layout::GlyphRasterConfig,
+    tiny_skia::{
+        IntSize,
+        Pixmap,
+    },
+    crate::{
+        Antialiasing,
+        Error,
+        GlyphCache,
+        GlyphKey,
+        Hinting,
+    },
+};
+
+const MAGIC: &[u8; 8] = b"TXTGLYC3";
+
+impl GlyphCache {
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(self.glyphs.len() as u64).to_le_bytes())?;
+        for (key, glyph) in &self.glyphs {
+            writer.write_all(&key.config.glyph_index.to_le_bytes())?;
+            writer.write_all(&key.config.px.to_le_bytes())?;
+            writer.write_all(&(key.config.font_hash as u64).to_le_bytes())?;
+            writer.write_all(&key.color)?;
+            writer.write_all(&[
+                match key.hinting {
+                    Hinting::None => 0,
+                    Hinting::Snap => 1,
+                    Hinting::Full => 2,
+                },
+                match key.antialiasing {
+                    Antialiasing::None => 0,
+                    Antialiasing::Grayscale => 1,
+                    Antialiasing::Supersampled => 2,
+                },
+            ])?;
+            writer.write_all(&key.stroke.to_le_bytes())?;
+            writer.write_all(&key.scale_x.to_le_bytes())?;
+            writer.write_all(&glyph.width().to_le_bytes())?;
+            writer.write_all(&glyph.height().to_le_bytes())?;
+            writer.write_all(glyph.data())?;
+        }
+        Ok(())
+    }
+
+    pub fn read_from(mut reader: impl Read) -> Result<Self, Error> {
+        fn read<const N: usize>(reader: &mut impl Read) -> Result<[u8; N], Error> {
+            let mut buf = [0; N];
+            reader.read_exact(&mut buf)?;
+            Ok(buf)
+        }
+
+        if read::<8>(&mut reader)? != *MAGIC { return Err(Error::CacheFormat) }
+        let mut cache = Self::default();
+        for _ in 0..u64::from_le_bytes(read(&mut reader)?) {
+            let key = GlyphKey {
+                config: GlyphRasterConfig {
+                    glyph_index: u16::from_le_bytes(read(&mut reader)?),
+                    px: f32::from_le_bytes(read(&mut reader)?),
+                    font_hash: u64::from_le_bytes(read(&mut reader)?) as usize,
+                },
+                color: read(&mut reader)?,
+                hinting: match read::<1>(&mut reader)? {
+                    [0] => Hinting::None,
+                    [1] => Hinting::Snap,
+                    [2] => Hinting::Full,
+                    _ => return Err(Error::CacheFormat),
+                },
+                antialiasing: match read::<1>(&mut reader)? {
+                    [0] => Antialiasing::None,
+                    [1] => Antialiasing::Grayscale,
+                    [2] => Antialiasing::Supersampled,
+                    _ => return Err(Error::CacheFormat),
+                },
+                stroke: u32::from_le_bytes(read(&mut reader)?),
+                scale_x: u32::from_le_bytes(read(&mut reader)?),
+            };
+            let width = u32::from_le_bytes(read(&mut reader)?);
+            let height = u32::from_le_bytes(read(&mut reader)?);
+            let size = IntSize::from_wh(width, height).ok_or(Error::CacheFormat)?;
+            // the dimensions aren't trusted to allocate up front, so a corrupt file fails when the data runs out
+            let len = u64::from(width).checked_mul(u64::from(height)).and_then(|len| len.checked_mul(4)).ok_or(Error::CacheFormat)?;
+            let mut data = Vec::default();
+            (&mut reader).take(len).read_to_end(&mut data)?;
+            if data.len() as u64 != len { return Err(Error::CacheFormat) }
+            let glyph = Pixmap::from_vec(data, size).ok_or(Error::CacheFormat)?;
+            cache.insert(key, glyph);
+        }
+        cache.reset_stats();
+        Ok(cache)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(glyph_index: u16) -> GlyphKey {
+        GlyphKey {
+            config: GlyphRasterConfig { glyph_index, px: 12.5, font_hash: 42 },
+            color: [1, 2, 3, 4],
+            hinting: Hinting::Snap,
+            antialiasing: Antialiasing::Supersampled,
+            stroke: 1.5f32.to_bits(),
+            scale_x: 0.75f32.to_bits(),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut cache = GlyphCache::default();
+        let mut glyph = Pixmap::new(2, 3).unwrap();
+        glyph.data_mut().iter_mut().enumerate().for_each(|(idx, byte)| *byte = idx as u8);
+        cache.insert(key(1), glyph.clone());
+        cache.insert(key(2), Pixmap::new(1, 1).unwrap());
+        let mut buf = Vec::default();
+        cache.write_to(&mut buf).unwrap();
+        let loaded = GlyphCache::read_from(&buf[..]).unwrap();
+        assert_eq!(loaded.glyphs.len(), 2);
+        assert_eq!((loaded.glyphs[&key(1)].width(), loaded.glyphs[&key(1)].height()), (2, 3));
+        assert_eq!(loaded.glyphs[&key(1)].data(), glyph.data());
+        assert_eq!(loaded.stats().bytes, cache.stats().bytes);
+    }
+
+    #[test]
+    fn reject_oversized_glyph() {
+        let mut cache = GlyphCache::default();
+        cache.insert(key(1), Pixmap::new(1, 1).unwrap());
+        let mut buf = Vec::default();
+        cache.write_to(&mut buf).unwrap();
+        assert!(matches!(GlyphCache::read_from(&buf[..buf.len() - 1]), Err(Error::CacheFormat)));
+        // claim a huge glyph without the data to back it
+        let dimensions = buf.len() - 4 - 8;
+        buf[dimensions..dimensions + 8].copy_from_slice(&[60_000u32.to_le_bytes(), 60_000u32.to_le_bytes()].concat());
+        assert!(matches!(GlyphCache::read_from(&buf[..]), Err(Error::CacheFormat)));
+    }
+}
@@ -0,0 +1,74 @@
+use {
+    std::{
+        ops::{
+            Deref,
+            DerefMut,
+        },
+        sync::{
+            Mutex,
+            PoisonError,
+        },
+    },
+    fontdue::layout::{
+        CoordinateSystem,
+        Layout,
+    },
+};
+
+/// A pool of reusable layouts, so rendering in a hot loop or from multiple threads doesn't allocate a new layout per text box.
+#[derive(Default)]
+pub struct LayoutPool(Mutex<Vec<Layout>>);
+
+impl LayoutPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes a layout from the pool, or creates a new one if all layouts are in use. The layout is returned to the pool when the guard is dropped.
+    pub fn get(&self) -> PooledLayout<'_> {
+        let layout = self.0.lock().unwrap_or_else(PoisonError::into_inner).pop().unwrap_or_else(|| Layout::new(CoordinateSystem::PositiveYDown));
+        PooledLayout { pool: self, layout: Some(layout) }
+    }
+
+    /// The number of layouts currently available for reuse.
+    pub fn len(&self) -> usize {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).clear();
+    }
+}
+
+/// A layout borrowed from a [`LayoutPool`].
+pub struct PooledLayout<'p> {
+    pool: &'p LayoutPool,
+    /// Always `Some` until dropped.
+    layout: Option<Layout>,
+}
+
+impl Deref for PooledLayout<'_> {
+    type Target = Layout;
+
+    fn deref(&self) -> &Layout {
+        self.layout.as_ref().expect("pooled layout used after drop")
+    }
+}
+
+impl DerefMut for PooledLayout<'_> {
+    fn deref_mut(&mut self) -> &mut Layout {
+        self.layout.as_mut().expect("pooled layout used after drop")
+    }
+}
+
+impl Drop for PooledLayout<'_> {
+    fn drop(&mut self) {
+        if let Some(layout) = self.layout.take() {
+            self.pool.0.lock().unwrap_or_else(PoisonError::into_inner).push(layout);
+        }
+    }
+}
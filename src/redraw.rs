@@ -0,0 +1,77 @@
+use {
+    alloc::vec::Vec,
+    fontdue::layout::GlyphPosition,
+    tiny_skia::{
+        BlendMode,
+        Color,
+        FillRule,
+        Mask,
+        Paint,
+        PathBuilder,
+        PixmapMut,
+        Rect,
+        Transform,
+    },
+    crate::{
+        Error,
+        GlyphCache,
+        TextBox,
+        union,
+    },
+};
+#[cfg(not(any(feature = "std", test)))] use crate::float::Float as _;
+
+impl TextBox<'_, '_> {
+    fn line_glyphs(&self, line: usize) -> &[GlyphPosition] {
+        self.lines.get(line).and_then(|line| self.glyphs.get(line.glyph_start..=line.glyph_end)).unwrap_or_default()
+    }
+
+    /// The area a line can touch when drawn, including effects and decorations that extend past its glyphs.
+    pub(crate) fn line_rect(&self, idx: usize) -> Option<Rect> {
+        let line = self.lines.get(idx)?;
+        let glyphs = self.line_glyphs(idx);
+        let baseline = line.baseline_y + self.y_offset;
+        let line_box = glyphs.iter()
+            .map(|glyph| self.glyph_extent(glyph))
+            .reduce(|(left1, right1), (left2, right2)| (left1.min(left2), right1.max(right2)))
+            .and_then(|(left, right)| Rect::from_ltrb(left, baseline - line.max_ascent, right, baseline - line.min_descent));
+        let rect = glyphs.iter()
+            .filter(|glyph| glyph.width > 0 && glyph.height > 0)
+            .fold(line_box, |rect, glyph| union(rect, self.glyph_rect(glyph)))?;
+        let margin = self.glow.map_or(0.0, |(_, radius)| radius.ceil())
+            .max(self.emboss.map_or(0.0, |(_, _, depth)| depth.abs().ceil()))
+            .max(self.decorations.iter().map(|decoration| 3.0 * decoration.thickness.unwrap_or(self.size / 16.0).max(1.0) + self.size / 10.0).fold(0.0, f32::max));
+        rect.outset(margin, margin)
+    }
+
+    /// Indices of the lines whose glyphs differ from those of `previous`.
+    pub fn changed_lines(&self, previous: &TextBox<'_, '_>) -> Vec<usize> {
+        (0..self.lines.len().max(previous.lines.len())).filter(|&line| {
+            let (old, new) = (previous.line_glyphs(line), self.line_glyphs(line));
+            old.len() != new.len() || old.iter().zip(new).any(|(old, new)| old.key != new.key || old.x != new.x || old.y != new.y)
+        }).collect()
+    }
+
+    /// Updates a canvas on which `previous` was drawn by redrawing only the lines that changed, and returns the damaged rect.
+    ///
+    /// The damaged area is first filled with `background`. Both text boxes should have been built with the same settings.
+    pub fn redraw(&self, previous: &TextBox<'_, '_>, mut canvas: PixmapMut<'_>, glyph_cache: &mut GlyphCache, background: Color) -> Result<Option<Rect>, Error> {
+        if let Some((c, byte_offset)) = self.control_char {
+            return Err(Error::ControlChar { c, byte_offset })
+        }
+        let (width, height) = (canvas.width(), canvas.height());
+        let Some(damage) = self.changed_lines(previous).into_iter()
+            .fold(None, |damage, line| union(union(damage, previous.line_rect(line)), self.line_rect(line)))
+            .and_then(|damage| damage.round_out())
+            .and_then(|damage| damage.to_rect().intersect(&Rect::from_xywh(0.0, 0.0, width as f32, height as f32)?))
+        else { return Ok(None) };
+        let mut mask = Mask::new(width, height).ok_or(Error::Canvas { width, height })?;
+        mask.fill_path(&PathBuilder::from_rect(damage), FillRule::Winding, false, Transform::identity());
+        let mut paint = Paint::default();
+        paint.set_color(background);
+        paint.blend_mode = BlendMode::Source;
+        canvas.fill_rect(damage, &paint, Transform::identity(), None);
+        self.draw_clipped(canvas, glyph_cache, Transform::identity(), Some(damage), Some(&mask))?;
+        Ok(Some(damage))
+    }
+}
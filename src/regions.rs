@@ -0,0 +1,68 @@
+use {
+    alloc::vec::Vec,
+    core::ops::Range,
+    fontdue::layout::LinePosition,
+    tiny_skia::Rect,
+    crate::{
+        TextBox,
+        unique,
+    },
+};
+
+impl TextBox<'_, '_> {
+    /// Byte offsets of each glyph into the full text appended so far.
+    ///
+    /// The layout reports offsets relative to each `append` call, so a new call is detected by the offset going back.
+    pub(crate) fn glyph_byte_offsets(&self) -> Vec<usize> {
+        let mut base = 0;
+        let mut prev = None::<(usize, char)>;
+        self.glyphs.iter().map(|glyph| {
+            if let Some((prev_offset, prev_char)) = prev {
+                if glyph.byte_offset <= prev_offset {
+                    base += prev_offset + prev_char.len_utf8();
+                }
+            }
+            prev = Some((glyph.byte_offset, glyph.parent));
+            base + glyph.byte_offset
+        }).collect()
+    }
+
+    /// The rectangles covering the glyphs whose text starts within the given byte range, one per line.
+    ///
+    /// This is the basis for selection highlighting, annotations, and search results. Byte offsets refer to the text appended so far, after preprocessing such as newline normalization.
+    pub fn rects_for_range(&self, range: Range<usize>) -> Vec<Rect> {
+        self.rects_for_range_with(&self.glyph_byte_offsets(), &range)
+    }
+
+    fn rects_for_range_with(&self, offsets: &[usize], range: &Range<usize>) -> Vec<Rect> {
+        self.lines.iter().filter_map(|line| {
+            let (left, right) = (line.glyph_start..=line.glyph_end)
+                .filter(|&idx| offsets.get(idx).is_some_and(|offset| range.contains(offset)))
+                .map(|idx| self.glyph_extent(&self.glyphs[idx]))
+                .reduce(|(left1, right1), (left2, right2)| (left1.min(left2), right1.max(right2)))?;
+            self.span_rect(line, left, right)
+        }).collect()
+    }
+
+    /// The rectangles covering the text tagged with `tag` via `Builder::region`, one per line per region.
+    pub fn region_rects(&self, tag: &str) -> Vec<Rect> {
+        let offsets = self.glyph_byte_offsets();
+        self.regions.iter()
+            .filter(|(_, region_tag)| region_tag == tag)
+            .flat_map(|(range, _)| self.rects_for_range_with(&offsets, range))
+            .collect()
+    }
+
+    /// The rectangle spanning the full height of a line between two horizontal positions.
+    pub(crate) fn span_rect(&self, line: &LinePosition, left: f32, right: f32) -> Option<Rect> {
+        let baseline = line.baseline_y + self.y_offset;
+        Rect::from_ltrb(left, baseline - line.max_ascent, right, baseline - line.min_descent)
+    }
+
+    /// The tags of all regions containing the given point, e.g. for hit testing links.
+    pub fn regions_at(&self, x: f32, y: f32) -> Vec<&str> {
+        unique(self.regions.iter().map(|(_, tag)| &**tag))
+            .filter(|tag| self.region_rects(tag).iter().any(|rect| rect.left() <= x && x < rect.right() && rect.top() <= y && y < rect.bottom()))
+            .collect()
+    }
+}
@@ -0,0 +1,262 @@
+use {
+    alloc::{
+        vec,
+        vec::Vec,
+    },
+    fontdue::{
+        Font,
+        layout::GlyphRasterConfig,
+    },
+    hashbrown::HashMap,
+    tiny_skia::{
+        ColorU8,
+        Pixmap,
+    },
+    crate::{
+        Error,
+        GlyphCache,
+        entry_bytes,
+    },
+};
+#[cfg(not(any(feature = "std", test)))] use crate::float::Float as _;
+
+const RED: u8 = 0b001;
+const GREEN: u8 = 0b010;
+const BLUE: u8 = 0b100;
+const WHITE: u8 = RED | GREEN | BLUE;
+const EDGE_COLORS: [u8; 3] = [GREEN | BLUE, RED | BLUE, RED | GREEN];
+const CORNER_THRESHOLD: f32 = 0.141;
+
+type Point = [f32; 2];
+
+fn sub([ax, ay]: Point, [bx, by]: Point) -> Point { [ax - bx, ay - by] }
+fn dot([ax, ay]: Point, [bx, by]: Point) -> f32 { ax * bx + ay * by }
+fn cross([ax, ay]: Point, [bx, by]: Point) -> f32 { ax * by - ay * bx }
+
+fn normalize(v: Point) -> Point {
+    let len = dot(v, v).sqrt();
+    if len == 0.0 { v } else { [v[0] / len, v[1] / len] }
+}
+
+struct Edge {
+    points: Vec<Point>,
+    color: u8,
+}
+
+impl Edge {
+    fn start_direction(&self) -> Point {
+        normalize(sub(self.points[1], self.points[0]))
+    }
+
+    fn end_direction(&self) -> Point {
+        let n = self.points.len();
+        normalize(sub(self.points[n - 1], self.points[n - 2]))
+    }
+}
+
+#[derive(Default)]
+struct Shape {
+    contours: Vec<Vec<Edge>>,
+    current: Vec<Edge>,
+    last: Point,
+}
+
+impl Shape {
+    fn push(&mut self, points: Vec<Point>) {
+        if points.windows(2).any(|pair| pair[0] != pair[1]) {
+            self.last = points[points.len() - 1];
+            self.current.push(Edge { points, color: WHITE });
+        }
+    }
+
+    fn color_edges(&mut self) {
+        for contour in &mut self.contours {
+            let corners = (0..contour.len())
+                .filter(|&idx| {
+                    let incoming = contour[(idx + contour.len() - 1) % contour.len()].end_direction();
+                    let outgoing = contour[idx].start_direction();
+                    dot(incoming, outgoing) <= 0.0 || cross(incoming, outgoing).abs() > CORNER_THRESHOLD
+                })
+                .collect::<Vec<_>>();
+            match corners[..] {
+                [] => for edge in contour.iter_mut() {
+                    edge.color = WHITE;
+                },
+                [corner] => {
+                    let len = contour.len();
+                    for offset in 0..len {
+                        contour[(corner + offset) % len].color = EDGE_COLORS[(offset * 3 / len).min(2)];
+                    }
+                }
+                [first, ..] => {
+                    let len = contour.len();
+                    let mut color_idx = 0;
+                    for offset in 0..len {
+                        let idx = (first + offset) % len;
+                        if offset > 0 && corners.contains(&idx) {
+                            color_idx = (color_idx + 1) % 3;
+                            if (first + offset + 1..first + len).all(|later| !corners.contains(&(later % len))) && color_idx == 0 {
+                                color_idx = 1;
+                            }
+                        }
+                        contour[idx].color = EDGE_COLORS[color_idx];
+                    }
+                }
+            }
+        }
+    }
+
+    fn segments(&self) -> impl Iterator<Item = (Point, Point, u8)> + '_ {
+        self.contours.iter().flatten().flat_map(|edge| edge.points.windows(2).map(|pair| (pair[0], pair[1], edge.color)))
+    }
+
+    fn winding(&self, p: Point) -> i32 {
+        self.segments().map(|(a, b, _)| {
+            if a[1] <= p[1] {
+                if b[1] > p[1] && cross(sub(b, a), sub(p, a)) > 0.0 { 1 } else { 0 }
+            } else if b[1] <= p[1] && cross(sub(b, a), sub(p, a)) < 0.0 { -1 } else { 0 }
+        }).sum()
+    }
+}
+
+impl ttf_parser::OutlineBuilder for Shape {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.close();
+        self.last = [x, y];
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.push(vec![self.last, [x, y]]);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let [x0, y0] = self.last;
+        self.push((0..=8).map(|step| {
+            let t = step as f32 / 8.0;
+            let u = 1.0 - t;
+            [u * u * x0 + 2.0 * u * t * x1 + t * t * x, u * u * y0 + 2.0 * u * t * y1 + t * t * y]
+        }).collect());
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let [x0, y0] = self.last;
+        self.push((0..=12).map(|step| {
+            let t = step as f32 / 12.0;
+            let u = 1.0 - t;
+            [
+                u * u * u * x0 + 3.0 * u * u * t * x1 + 3.0 * u * t * t * x2 + t * t * t * x,
+                u * u * u * y0 + 3.0 * u * u * t * y1 + 3.0 * u * t * t * y2 + t * t * t * y,
+            ]
+        }).collect());
+    }
+
+    fn close(&mut self) {
+        if let (Some(first), Some(last)) = (self.current.first(), self.current.last()) {
+            let (start, end) = (first.points[0], last.points[last.points.len() - 1]);
+            if start != end {
+                self.push(vec![end, start]);
+            }
+        }
+        if !self.current.is_empty() {
+            self.contours.push(core::mem::take(&mut self.current));
+        }
+    }
+}
+
+/// Returns the signed distance and orthogonality of `p` relative to the segment from `a` to `b`.
+fn segment_distance(p: Point, a: Point, b: Point) -> (f32, f32) {
+    let ab = sub(b, a);
+    let t = (dot(sub(p, a), ab) / dot(ab, ab)).clamp(0.0, 1.0);
+    let nearest = [a[0] + ab[0] * t, a[1] + ab[1] * t];
+    let offset = sub(p, nearest);
+    let distance = dot(offset, offset).sqrt();
+    let side = cross(ab, sub(p, a));
+    let orthogonality = if distance == 0.0 { 1.0 } else { (side / (dot(ab, ab).sqrt() * distance)).abs() };
+    (if side < 0.0 { distance } else { -distance }, orthogonality)
+}
+
+fn generate(font_data: &[u8], glyph_index: u16, px: f32, range: f32, multichannel: bool) -> Result<Option<Pixmap>, Error> {
+    let face = ttf_parser::Face::parse(font_data, 0)?;
+    let mut shape = Shape::default();
+    let Some(bbox) = face.outline_glyph(ttf_parser::GlyphId(glyph_index), &mut shape) else { return Ok(None) };
+    ttf_parser::OutlineBuilder::close(&mut shape);
+    if multichannel {
+        shape.color_edges();
+    }
+    let scale = px / f32::from(face.units_per_em());
+    let width = (f32::from(bbox.width()) * scale + 2.0 * range).ceil() as u32;
+    let height = (f32::from(bbox.height()) * scale + 2.0 * range).ceil() as u32;
+    let mut pixmap = Pixmap::new(width, height).ok_or(Error::Canvas { width, height })?;
+    for (idx, pixel) in pixmap.pixels_mut().iter_mut().enumerate() {
+        let (x, y) = (idx as u32 % width, idx as u32 / width);
+        let p = [
+            f32::from(bbox.x_min) + (x as f32 + 0.5 - range) / scale,
+            f32::from(bbox.y_max) - (y as f32 + 0.5 - range) / scale,
+        ];
+        let mut channels = [(f32::INFINITY, 0.0, 0.0); 3];
+        for (a, b, color) in shape.segments() {
+            let (distance, orthogonality) = segment_distance(p, a, b);
+            for (channel, nearest) in channels.iter_mut().enumerate() {
+                if color & (1 << channel) != 0 {
+                    let closer = distance.abs() < nearest.0 - f32::EPSILON
+                        || (distance.abs() - nearest.0).abs() <= f32::EPSILON && orthogonality > nearest.2;
+                    if closer {
+                        *nearest = (distance.abs(), distance, orthogonality);
+                    }
+                }
+            }
+        }
+        let mut distances = channels.map(|(_, distance, _)| distance * scale);
+        let mut sorted = distances;
+        sorted.sort_by(f32::total_cmp);
+        let inside = shape.winding(p) != 0;
+        if (sorted[1] > 0.0) != inside {
+            distances = distances.map(|distance| -distance);
+        }
+        let [red, green, blue] = distances.map(|distance| ((distance / range + 0.5).clamp(0.0, 1.0) * 255.0).round() as u8);
+        *pixel = if multichannel {
+            ColorU8::from_rgba(red, green, blue, u8::MAX)
+        } else {
+            ColorU8::from_rgba(u8::MAX, u8::MAX, u8::MAX, red)
+        }.premultiply();
+    }
+    Ok(Some(pixmap))
+}
+
+/// Generates a single-channel signed distance field, stored in the alpha channel.
+pub fn sdf_glyph(font_data: &[u8], glyph_index: u16, px: f32, range: f32) -> Result<Option<Pixmap>, Error> {
+    generate(font_data, glyph_index, px, range, false)
+}
+
+/// Generates a multi-channel signed distance field in the red, green, and blue channels. The distance is the median of the three channels.
+pub fn msdf_glyph(font_data: &[u8], glyph_index: u16, px: f32, range: f32) -> Result<Option<Pixmap>, Error> {
+    generate(font_data, glyph_index, px, range, true)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct DistanceFieldKey {
+    config: GlyphRasterConfig,
+    range: u32,
+    multichannel: bool,
+}
+
+pub(crate) type DistanceFieldCache = HashMap<DistanceFieldKey, Option<Pixmap>>;
+
+impl GlyphCache {
+    pub fn distance_field(&mut self, font: &Font, font_data: &[u8], glyph_index: u16, px: f32, range: f32, multichannel: bool) -> Result<Option<&Pixmap>, Error> {
+        let key = DistanceFieldKey {
+            config: GlyphRasterConfig { glyph_index, px, font_hash: font.file_hash() },
+            range: range.to_bits(),
+            multichannel,
+        };
+        if self.distance_fields.contains_key(&key) {
+            self.hits += 1;
+        } else {
+            let glyph = generate(font_data, glyph_index, px, range, multichannel)?;
+            self.misses += 1;
+            self.bytes += entry_bytes::<DistanceFieldKey, Option<Pixmap>>(glyph.as_ref());
+            self.distance_fields.insert(key, glyph);
+        }
+        Ok(self.distance_fields[&key].as_ref())
+    }
+}
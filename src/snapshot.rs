@@ -0,0 +1,110 @@
+use {
+    alloc::vec::Vec,
+    fontdue::Font,
+    tiny_skia::{
+        BlendMode,
+        PixmapMut,
+        PixmapPaint,
+        Rect,
+        Transform,
+    },
+    crate::{
+        Error,
+        GlyphCache,
+        GlyphKey,
+        TextBox,
+        rasterize,
+        union,
+    },
+};
+
+/// A glyph captured by [`TextBox::snapshot`], with the cache keys of its layers, bottom to top.
+#[derive(Debug, Clone, PartialEq)]
+struct SnapshotGlyph {
+    x: f32,
+    y: f32,
+    width: usize,
+    height: usize,
+    font_index: usize,
+    layers: Vec<(GlyphKey, f32)>,
+}
+
+/// The positioned glyphs of a text box, detached from its font, text, and layout so it can be sent to another thread and drawn there.
+///
+/// Only the glyphs themselves (including their stroke and per-range colors) are captured. Backgrounds, stripes, decorations, and effects are not.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextBoxSnapshot {
+    glyphs: Vec<SnapshotGlyph>,
+    font_hash: usize,
+    inner_bounds: Rect,
+    opacity: f32,
+    blend_mode: BlendMode,
+}
+
+impl TextBox<'_, '_> {
+    /// Captures the laid out glyphs for drawing later with [`TextBoxSnapshot::draw`].
+    pub fn snapshot(&self) -> Result<TextBoxSnapshot, Error> {
+        if let Some((c, byte_offset)) = self.control_char {
+            return Err(Error::ControlChar { c, byte_offset })
+        }
+        Ok(TextBoxSnapshot {
+            glyphs: self.glyphs.iter().enumerate()
+                .filter(|(_, glyph)| glyph.width > 0 && glyph.height > 0)
+                .map(|(idx, glyph)| SnapshotGlyph {
+                    x: glyph.x,
+                    y: glyph.y,
+                    width: glyph.width,
+                    height: glyph.height,
+                    font_index: glyph.font_index,
+                    layers: self.glyph_layers(idx).collect(),
+                })
+                .collect(),
+            font_hash: self.font.file_hash(),
+            inner_bounds: self.inner_bounds,
+            opacity: self.opacity,
+            blend_mode: self.blend_mode,
+        })
+    }
+}
+
+impl TextBoxSnapshot {
+    /// The inner bounds of the text box the snapshot was taken of.
+    pub fn inner_bounds(&self) -> Rect {
+        self.inner_bounds
+    }
+
+    /// The area covered by the glyphs, including their strokes.
+    pub fn glyph_bounds(&self) -> Option<Rect> {
+        self.glyphs.iter().fold(None, |bounds, glyph| {
+            let padding = glyph.layers.iter().map(|&(_, padding)| padding).fold(0.0, f32::max);
+            union(bounds, Rect::from_xywh(glyph.x - padding, glyph.y - padding, glyph.width as f32 + 2.0 * padding, glyph.height as f32 + 2.0 * padding))
+        })
+    }
+
+    /// Draws the captured glyphs. `font` must be the font the text box was laid out with, since it's needed to rasterize glyphs missing from the cache.
+    pub fn draw(&self, mut canvas: PixmapMut<'_>, font: &Font, glyph_cache: &mut GlyphCache) -> Result<Option<Rect>, Error> {
+        if font.file_hash() != self.font_hash {
+            return Err(Error::FontMismatch)
+        }
+        let pixmap_paint = PixmapPaint {
+            opacity: self.opacity.clamp(0.0, 1.0),
+            blend_mode: self.blend_mode,
+            ..PixmapPaint::default()
+        };
+        let mut dirty = None;
+        for glyph in &self.glyphs {
+            for &(key, padding) in &glyph.layers {
+                let glyph_canvas = if glyph_cache.glyphs.contains_key(&key) {
+                    glyph_cache.hits += 1;
+                    &glyph_cache.glyphs[&key]
+                } else {
+                    glyph_cache.misses += 1;
+                    glyph_cache.insert(key, rasterize(font, glyph.font_index, [glyph.width, glyph.height], key)?)
+                };
+                canvas.draw_pixmap(0, 0, glyph_canvas.as_ref(), &pixmap_paint, Transform::from_translate(glyph.x - padding, glyph.y - padding), None);
+                dirty = union(dirty, Rect::from_xywh(glyph.x - padding, glyph.y - padding, glyph.width as f32 + 2.0 * padding, glyph.height as f32 + 2.0 * padding));
+            }
+        }
+        Ok(dirty.and_then(|dirty| dirty.intersect(&Rect::from_xywh(0.0, 0.0, canvas.width() as f32, canvas.height() as f32)?)))
+    }
+}
@@ -0,0 +1,56 @@
+use {
+    tiny_skia::{
+        ColorU8,
+        Mask,
+        PixmapMut,
+        Rect,
+        Transform,
+    },
+    crate::{
+        TextBox,
+        union,
+    },
+};
+#[cfg(not(any(feature = "std", test)))] use crate::float::Float as _;
+
+/// How far zebra stripes extend horizontally.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum StripeWidth {
+    /// Across the full width of the inner bounds, like table rows.
+    #[default]
+    Bounds,
+    /// Only behind the text of each line, like highlighted list items.
+    Text,
+}
+
+impl TextBox<'_, '_> {
+    /// The rectangle behind a line for its stripe, aligned to whole pixels so adjacent stripes don't overlap or leave gaps.
+    fn stripe_rect(&self, line_idx: usize, width: StripeWidth) -> Option<Rect> {
+        let line = self.lines.get(line_idx)?;
+        let top = line.baseline_y + self.y_offset - line.max_ascent;
+        let (left, right) = match width {
+            StripeWidth::Bounds => (self.inner_bounds.left(), self.inner_bounds.right()),
+            StripeWidth::Text => {
+                let glyphs = self.glyphs.get(line.glyph_start..=line.glyph_end)?;
+                let mut extents = glyphs.iter().filter(|glyph| !glyph.parent.is_whitespace()).map(|glyph| self.glyph_extent(glyph));
+                let (left, right) = extents.next()?;
+                extents.fold((left, right), |(left, right), (glyph_left, glyph_right)| (left.min(glyph_left), right.max(glyph_right)))
+            }
+        };
+        Rect::from_ltrb(left.round(), top.round(), right.round(), (top + line.max_new_line_size).round())
+    }
+
+    /// Draws alternating backgrounds behind the lines, if enabled, and returns the area they cover.
+    pub(crate) fn draw_stripes(&self, canvas: &mut PixmapMut<'_>, transform: Transform, mask: Option<&Mask>) -> Option<Rect> {
+        let ([even, odd], width): ([ColorU8; 2], StripeWidth) = self.stripes?;
+        let mut dirty = None;
+        for line_idx in 0..self.lines.len() {
+            let Some(rect) = self.stripe_rect(line_idx, width) else { continue };
+            let color = if line_idx % 2 == 0 { even } else { odd };
+            canvas.fill_rect(rect, &self.paint(color), transform, mask);
+            dirty = union(dirty, rect.transform(transform));
+        }
+        dirty
+    }
+}
@@ -0,0 +1,318 @@
+use {
+    alloc::{
+        borrow::ToOwned as _,
+        string::String,
+        vec::Vec,
+    },
+    core::str::FromStr,
+    fontdue::{
+        Font,
+        layout::HorizontalAlign,
+    },
+    hashbrown::HashMap,
+    crate::{
+        Antialiasing,
+        Bounds,
+        Builder,
+        ControlChars,
+        Decoration,
+        DecorationLine,
+        DefaultBounds,
+        Error,
+        FontStretch,
+        Hinting,
+        LineStyle,
+        MissingGlyphs,
+        PixelRounding,
+        Size,
+        StripeWidth,
+        Truncation,
+        TryIntoColor as _,
+        VerticalAlign,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum HAlign {
+    Left,
+    Center,
+    Right,
+    /// Left for left-to-right text, right for right-to-left text.
+    Start,
+    /// Right for left-to-right text, left for right-to-left text.
+    End,
+}
+
+/// The base direction of a paragraph.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum Direction {
+    /// Determined by the first strongly directional character of the text. Text without any strongly directional characters is treated as left-to-right.
+    #[default]
+    Auto,
+    Ltr,
+    Rtl,
+}
+
+impl Direction {
+    pub fn is_rtl(self, text: &str) -> bool {
+        match self {
+            Self::Auto => unicode_bidi::get_base_direction_full(text) == unicode_bidi::Direction::Rtl,
+            Self::Ltr => false,
+            Self::Rtl => true,
+        }
+    }
+}
+
+impl HAlign {
+    /// Resolves `Start` and `End` using the given base direction of the text.
+    pub fn resolve(self, direction: Direction, text: &str) -> HorizontalAlign {
+        let rtl = || direction.is_rtl(text);
+        match self {
+            Self::Left => HorizontalAlign::Left,
+            Self::Center => HorizontalAlign::Center,
+            Self::Right => HorizontalAlign::Right,
+            Self::Start => if rtl() { HorizontalAlign::Right } else { HorizontalAlign::Left },
+            Self::End => if rtl() { HorizontalAlign::Left } else { HorizontalAlign::Right },
+        }
+    }
+}
+
+impl From<HorizontalAlign> for HAlign {
+    fn from(halign: HorizontalAlign) -> Self {
+        match halign {
+            HorizontalAlign::Left => Self::Left,
+            HorizontalAlign::Center => Self::Center,
+            HorizontalAlign::Right => Self::Right,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default, rename_all = "snake_case"))]
+pub struct Style {
+    pub size: Option<Size>,
+    pub color: Option<String>,
+    pub halign: Option<HAlign>,
+    pub direction: Option<Direction>,
+    pub valign: Option<VerticalAlign>,
+    pub hanging_punctuation: Option<bool>,
+    pub hinting: Option<Hinting>,
+    pub antialiasing: Option<Antialiasing>,
+    pub opacity: Option<f32>,
+    /// Synthesizes a bold weight by stroking the glyphs in the text color, for fonts without a bold face. An explicit `stroke` takes precedence.
+    pub bold: Option<bool>,
+    pub stroke: Option<StrokeStyle>,
+    pub glow: Option<GlowStyle>,
+    pub emboss: Option<EmbossStyle>,
+    /// Added to the decorations of the builder.
+    pub decorations: Vec<DecorationStyle>,
+    pub tracking: Option<Size>,
+    pub stretch: Option<FontStretch>,
+    pub truncation: Option<Truncation>,
+    /// See [`Builder::fade_overflow`].
+    pub fade_overflow: Option<f32>,
+    pub stripes: Option<StripesStyle>,
+    pub missing_glyphs: Option<MissingGlyphs>,
+    pub control_chars: Option<ControlChars>,
+    pub nfc: Option<bool>,
+    pub pixel_rounding: Option<PixelRounding>,
+}
+
+/// See [`Builder::stroke`]. Colors in styles are strings parsed like [`Builder::try_color`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub struct StrokeStyle {
+    pub color: String,
+    pub width: Size,
+}
+
+/// See [`Builder::glow`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub struct GlowStyle {
+    pub color: String,
+    pub radius: f32,
+}
+
+/// See [`Builder::emboss`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub struct EmbossStyle {
+    pub highlight: String,
+    pub shadow: String,
+    pub depth: f32,
+}
+
+/// See [`Decoration`].
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default, rename_all = "snake_case"))]
+pub struct DecorationStyle {
+    pub line: DecorationLine,
+    pub style: LineStyle,
+    /// Defaults to the text color.
+    pub color: Option<String>,
+    /// Defaults to a sixteenth of the font size.
+    pub thickness: Option<f32>,
+}
+
+impl DecorationStyle {
+    fn to_decoration(&self) -> Result<Decoration, Error> {
+        Ok(Decoration {
+            line: self.line,
+            style: self.style,
+            color: self.color.as_deref().map(|color| color.try_into_color_u8()).transpose()?,
+            thickness: self.thickness,
+        })
+    }
+}
+
+/// See [`Builder::zebra_stripes`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub struct StripesStyle {
+    pub even: String,
+    pub odd: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub width: StripeWidth,
+}
+
+/// The stroke width used to synthesize a bold weight, relative to the font size.
+const FAUX_BOLD_WIDTH: Size = Size::Em(1.0 / 32.0);
+
+impl FromStr for Style {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let mut style = Self::default();
+        for token in s.split_whitespace() {
+            match token {
+                "left" => style.halign = Some(HAlign::Left),
+                "center" => style.halign = Some(HAlign::Center),
+                "right" => style.halign = Some(HAlign::Right),
+                "start" => style.halign = Some(HAlign::Start),
+                "end" => style.halign = Some(HAlign::End),
+                "ltr" => style.direction = Some(Direction::Ltr),
+                "rtl" => style.direction = Some(Direction::Rtl),
+                "top" => style.valign = Some(VerticalAlign::Top),
+                "middle" => style.valign = Some(VerticalAlign::Middle),
+                "bottom" => style.valign = Some(VerticalAlign::Bottom),
+                "hanging" => style.hanging_punctuation = Some(true),
+                "hinted" => style.hinting = Some(Hinting::Full),
+                "aliased" => style.antialiasing = Some(Antialiasing::None),
+                "supersampled" => style.antialiasing = Some(Antialiasing::Supersampled),
+                "bold" => style.bold = Some(true),
+                "underline" => style.decorations.push(DecorationStyle { line: DecorationLine::Underline, ..DecorationStyle::default() }),
+                "overline" => style.decorations.push(DecorationStyle { line: DecorationLine::Overline, ..DecorationStyle::default() }),
+                "strikethrough" => style.decorations.push(DecorationStyle { line: DecorationLine::Strikethrough, ..DecorationStyle::default() }),
+                "ellipsis" => style.truncation = Some(Truncation::End),
+                "condensed" => style.stretch = Some(FontStretch::Condensed),
+                "expanded" => style.stretch = Some(FontStretch::Expanded),
+                _ => if token.starts_with('#') {
+                    token.try_into_color_u8()?;
+                    style.color = Some(token.to_owned());
+                } else if let Ok(size) = token.parse() {
+                    style.size = Some(size);
+                } else if let Some(opacity) = token.strip_suffix('%').and_then(|opacity| opacity.parse::<f32>().ok()) {
+                    style.opacity = Some(opacity / 100.0);
+                } else {
+                    return Err(Error::StyleToken(token.to_owned()))
+                },
+            }
+        }
+        Ok(style)
+    }
+}
+
+impl<B: Bounds> Builder<'_, '_, B> {
+    pub fn style(mut self, style: &Style) -> Result<Self, Error> {
+        if let Some(size) = style.size { self = self.size(size) }
+        if let Some(ref color) = style.color { self = self.try_color(color.as_str())? }
+        if let Some(halign) = style.halign { self = self.halign(halign) }
+        if let Some(direction) = style.direction { self = self.direction(direction) }
+        if let Some(valign) = style.valign { self = self.valign(valign) }
+        if let Some(hanging_punctuation) = style.hanging_punctuation { self = self.hanging_punctuation(hanging_punctuation) }
+        if let Some(hinting) = style.hinting { self = self.hinting(hinting) }
+        if let Some(antialiasing) = style.antialiasing { self = self.antialiasing(antialiasing) }
+        if let Some(opacity) = style.opacity { self = self.opacity(opacity) }
+        if let Some(ref stroke) = style.stroke {
+            self = self.stroke(stroke.color.as_str().try_into_color_u8()?, stroke.width);
+        } else if style.bold == Some(true) {
+            self.stroke = Some((None, FAUX_BOLD_WIDTH));
+        }
+        if let Some(ref glow) = style.glow { self = self.glow(glow.color.as_str().try_into_color_u8()?, glow.radius) }
+        if let Some(ref emboss) = style.emboss { self = self.emboss(emboss.highlight.as_str().try_into_color_u8()?, emboss.shadow.as_str().try_into_color_u8()?, emboss.depth) }
+        for decoration in &style.decorations { self = self.decoration(decoration.to_decoration()?) }
+        if let Some(tracking) = style.tracking { self = self.tracking(tracking) }
+        if let Some(stretch) = style.stretch { self = self.font_stretch(stretch) }
+        if let Some(truncation) = style.truncation { self = self.truncate(truncation) }
+        if let Some(fade_overflow) = style.fade_overflow { self = self.fade_overflow(fade_overflow) }
+        if let Some(ref stripes) = style.stripes { self = self.zebra_stripes(stripes.even.as_str().try_into_color_u8()?, stripes.odd.as_str().try_into_color_u8()?, stripes.width) }
+        if let Some(missing_glyphs) = style.missing_glyphs { self = self.missing_glyphs(missing_glyphs) }
+        if let Some(control_chars) = style.control_chars { self = self.control_chars(control_chars) }
+        if let Some(nfc) = style.nfc { self = self.nfc(nfc) }
+        if let Some(pixel_rounding) = style.pixel_rounding { self = self.pixel_rounding(pixel_rounding) }
+        Ok(self)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(transparent))]
+pub struct Theme(HashMap<String, Style>);
+
+impl Theme {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, style: Style) -> Option<Style> {
+        self.0.insert(name.into(), style)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Style> {
+        self.0.get(name)
+    }
+}
+
+impl<S: Into<String>> FromIterator<(S, Style)> for Theme {
+    fn from_iter<I: IntoIterator<Item = (S, Style)>>(iter: I) -> Self {
+        Self(iter.into_iter().map(|(name, style)| (name.into(), style)).collect())
+    }
+}
+
+impl<'f, 't> Builder<'f, 't, DefaultBounds> {
+    pub fn from_theme(font: &'f Font, theme: &Theme, name: &str, text: &'t str) -> Result<Self, Error> {
+        Self::new(font, text).style(theme.get(name).ok_or_else(|| Error::UnknownStyle(name.to_owned()))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_style_string() {
+        let style = "24px bold #fff center".parse::<Style>().unwrap();
+        assert_eq!(style, Style {
+            size: Some(Size::Px(24.0)),
+            color: Some("#fff".to_owned()),
+            halign: Some(HAlign::Center),
+            bold: Some(true),
+            ..Style::default()
+        });
+    }
+
+    #[test]
+    fn parse_effect_tokens() {
+        let style = "underline ellipsis condensed".parse::<Style>().unwrap();
+        assert_eq!(style.decorations, [DecorationStyle { line: DecorationLine::Underline, ..DecorationStyle::default() }]);
+        assert_eq!(style.truncation, Some(Truncation::End));
+        assert_eq!(style.stretch, Some(FontStretch::Condensed));
+    }
+
+    #[test]
+    fn reject_unknown_token() {
+        assert!(matches!("24px heavy".parse::<Style>(), Err(Error::StyleToken(token)) if token == "heavy"));
+    }
+}
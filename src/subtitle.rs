@@ -0,0 +1,78 @@
+use {
+    fontdue::{
+        Font,
+        layout::{
+            CoordinateSystem,
+            HorizontalAlign,
+            Layout,
+            LayoutSettings,
+            TextStyle,
+        },
+    },
+    tiny_skia::Rect,
+    crate::{
+        Builder,
+        DefaultBounds,
+        Error,
+        InnerBounds,
+        VerticalAlign,
+        normalize_newlines,
+    },
+};
+#[cfg(not(any(feature = "std", test)))] use crate::float::Float as _;
+
+/// Margins kept clear of subtitles, as fractions of the frame size on each side.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SafeArea {
+    pub horizontal: f32,
+    pub vertical: f32,
+}
+
+impl Default for SafeArea {
+    fn default() -> Self {
+        Self { horizontal: 0.1, vertical: 0.05 }
+    }
+}
+
+fn line_count(layout: &mut Layout, font: &Font, text: &str, size: f32, max_width: f32) -> usize {
+    layout.reset(&LayoutSettings {
+        max_width: Some(max_width),
+        ..LayoutSettings::default()
+    });
+    layout.append(core::slice::from_ref(font), &TextStyle::new(text, size, 0));
+    layout.lines().map_or(0, |lines| lines.len())
+}
+
+impl<'f, 't> Builder<'f, 't, DefaultBounds> {
+    /// A preset for video subtitles: white text anchored to the bottom of the safe area, with a black outline and soft shadow.
+    ///
+    /// Wrapped lines are balanced by narrowing the text box as far as possible without adding another line.
+    pub fn subtitle(font: &'f Font, text: &'t str, [frame_width, frame_height]: [f32; 2], size: f32, safe_area: SafeArea) -> Result<Builder<'f, 't, InnerBounds>, Error> {
+        let (margin_x, margin_y) = (frame_width * safe_area.horizontal, frame_height * safe_area.vertical);
+        let safe = Rect::from_xywh(margin_x, margin_y, frame_width - 2.0 * margin_x, frame_height - 2.0 * margin_y)
+            .ok_or(Error::Rect { x: margin_x, y: margin_y, width: frame_width - 2.0 * margin_x, height: frame_height - 2.0 * margin_y })?;
+        let normalized = normalize_newlines(text);
+        let mut layout = Layout::new(CoordinateSystem::PositiveYDown);
+        let lines = line_count(&mut layout, font, &normalized, size, safe.width());
+        let (mut narrow, mut wide) = (0.0, safe.width());
+        while wide - narrow > 1.0 {
+            let mid = (narrow + wide) / 2.0;
+            if line_count(&mut layout, font, &normalized, size, mid) > lines {
+                narrow = mid;
+            } else {
+                wide = mid;
+            }
+        }
+        let width = wide.ceil().min(safe.width());
+        let bounds = Rect::from_xywh(safe.x() + (safe.width() - width) / 2.0, safe.y(), width, safe.height())
+            .ok_or(Error::Rect { x: safe.x(), y: safe.y(), width, height: safe.height() })?;
+        Ok(Self::new(font, text)
+            .size(size)
+            .color([u8::MAX; 3])
+            .halign(HorizontalAlign::Center)
+            .valign(VerticalAlign::Bottom)
+            .stroke([0u8; 3], (size / 16.0).max(1.0))
+            .glow([0, 0, 0, 0xc0], size / 4.0)
+            .bounds_inner(bounds))
+    }
+}
@@ -0,0 +1,125 @@
+use {
+    core::ops::Range,
+    fontdue::Font,
+    syntect::{
+        easy::HighlightLines,
+        highlighting::{
+            Color,
+            FontStyle,
+            Style,
+            Theme,
+        },
+        parsing::{
+            SyntaxReference,
+            SyntaxSet,
+        },
+        util::LinesWithEndings,
+    },
+    tiny_skia::ColorU8,
+    crate::{
+        Bounds,
+        Builder,
+        Decoration,
+        DecorationLine,
+        DefaultBounds,
+        Error,
+        IntoColor,
+        LineStyle,
+        continue_crlf,
+        normalize_newlines,
+    },
+};
+
+impl IntoColor for Color {
+    fn into_color_u8(self) -> ColorU8 {
+        ColorU8::from_rgba(self.r, self.g, self.b, self.a)
+    }
+}
+
+impl<'f, 't> Builder<'f, 't, DefaultBounds> {
+    /// Starts building a text box for code highlighted with syntect, using the theme's default foreground color for unstyled text.
+    pub fn highlighted(font: &'f Font, code: &'t str, syntax: &SyntaxReference, syntax_set: &SyntaxSet, theme: &Theme) -> Result<Self, Error> {
+        let mut highlighter = HighlightLines::new(syntax, theme);
+        let mut highlights = Vec::default();
+        for line in LinesWithEndings::from(code) {
+            highlights.extend(highlighter.highlight_line(line, syntax_set)?);
+        }
+        let mut builder = Self::new(font, code);
+        if let Some(foreground) = theme.settings.foreground {
+            builder = builder.color(foreground);
+        }
+        Ok(builder.syntect_highlights(&highlights))
+    }
+}
+
+/// The byte ranges of highlighted pieces in the text after newline normalization, which is what color and decoration ranges refer to.
+fn normalized_ranges<'a>(highlights: &'a [(Style, &str)]) -> impl Iterator<Item = (Style, Range<usize>)> + 'a {
+    let mut trailing_cr = false;
+    let mut start = 0;
+    highlights.iter().map(move |&(style, piece)| {
+        let range = start..start + normalize_newlines(continue_crlf(&mut trailing_cr, piece)).len();
+        start = range.end;
+        (style, range)
+    })
+}
+
+impl<B: Bounds> Builder<'_, '_, B> {
+    /// Colors and underlines the text according to pieces highlighted by syntect, e.g. from [`HighlightLines::highlight_line`].
+    /// The pieces must cover the text in order, starting at its beginning. Bold and italic font styles are ignored, since a text box uses a single font.
+    pub fn syntect_highlights(mut self, highlights: &[(Style, &str)]) -> Self {
+        for (style, range) in normalized_ranges(highlights) {
+            self = self.color_range(range.clone(), style.foreground);
+            if style.font_style.contains(FontStyle::UNDERLINE) {
+                self = self.decorate_range(range, Decoration::new(DecorationLine::Underline, LineStyle::Solid).color(style.foreground));
+            }
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        syntect::parsing::{
+            Scope,
+            SyntaxSetBuilder,
+            syntax_definition::{
+                Context,
+                SyntaxDefinition,
+            },
+        },
+        super::*,
+    };
+
+    #[test]
+    fn highlight_crlf() {
+        // like `SyntaxSetBuilder::add_plain_text_syntax`, which needs the `yaml-load` feature
+        let mut builder = SyntaxSetBuilder::new();
+        builder.add(SyntaxDefinition {
+            name: "Plain Text".to_owned(),
+            file_extensions: Vec::default(),
+            scope: Scope::new("text.plain").unwrap(),
+            first_line_match: None,
+            hidden: false,
+            variables: Default::default(),
+            // the YAML loader adds the `__start` context highlighting begins in
+            contexts: [("__start".to_owned(), Context::new(false)), ("main".to_owned(), Context::new(true))].into_iter().collect(),
+        });
+        let syntax_set = builder.build();
+        let theme = Theme::default();
+        let mut highlighter = HighlightLines::new(syntax_set.find_syntax_plain_text(), &theme);
+        let mut highlights = Vec::default();
+        for line in LinesWithEndings::from("one\r\ntwo\r\nthree") {
+            highlights.extend(highlighter.highlight_line(line, &syntax_set).unwrap());
+        }
+        let text = normalize_newlines("one\r\ntwo\r\nthree");
+        let pieces = normalized_ranges(&highlights).map(|(_, range)| &text[range]).collect::<Vec<_>>();
+        assert_eq!(pieces, ["one\n", "two\n", "three"]);
+    }
+
+    #[test]
+    fn highlight_crlf_split_across_pieces() {
+        let highlights = [(Style::default(), "a\r"), (Style::default(), "\nb")];
+        assert_eq!(normalized_ranges(&highlights).map(|(_, range)| range).collect::<Vec<_>>(), [0..2, 2..3]);
+    }
+}
@@ -0,0 +1,23 @@
+use {
+    tiny_skia::{
+        PixmapMut,
+        Rect,
+        Transform,
+    },
+    crate::{
+        Error,
+        GlyphCache,
+        TextBox,
+    },
+};
+
+impl TextBox<'_, '_> {
+    /// Draws the part of the text box that falls on one tile of a larger canvas whose top left corner is at `origin` in canvas coordinates.
+    ///
+    /// The returned rect is in tile coordinates. Glyphs that don't touch the tile are skipped, so a huge canvas can be rendered tile by tile.
+    pub fn draw_tile(&self, tile: PixmapMut<'_>, [x, y]: [i32; 2], glyph_cache: &mut GlyphCache) -> Result<Option<Rect>, Error> {
+        let clip = Rect::from_xywh(0.0, 0.0, tile.width() as f32, tile.height() as f32).ok_or(Error::Canvas { width: tile.width(), height: tile.height() })?;
+        // offsets are whole pixels so hinted positions stay on the pixel grid
+        self.draw_clipped(tile, glyph_cache, Transform::from_translate(-x as f32, -y as f32), Some(clip), None)
+    }
+}
@@ -0,0 +1,103 @@
+use {
+    alloc::{
+        borrow::{
+            Cow,
+            ToOwned as _,
+        },
+        string::String,
+        vec::Vec,
+    },
+    crate::{
+        HorizontalScale,
+        TextBox,
+    },
+};
+#[cfg(not(any(feature = "std", test)))] use crate::float::Float as _;
+
+const ELLIPSIS: char = '…';
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum Truncation {
+    /// Keeps the start of the line: `verylongfilename…`
+    #[default]
+    End,
+    /// Keeps the start and end of the line, e.g. for file paths and IDs: `verylongfilena…me.txt`
+    Middle,
+}
+
+impl Truncation {
+    /// The longest truncation of a line for which `fits` holds, found by binary search over the number of kept chars.
+    fn truncate_line(self, line: &str, fits: impl Fn(&[char]) -> bool) -> String {
+        let chars = line.chars().collect::<Vec<_>>();
+        let truncated = |kept: usize| {
+            let (head, tail) = match self {
+                Self::End => (kept, 0),
+                Self::Middle => (kept.div_ceil(2), kept / 2),
+            };
+            chars[..head].iter().copied()
+                .chain([ELLIPSIS])
+                .chain(chars[chars.len() - tail..].iter().copied())
+                .collect::<Vec<_>>()
+        };
+        let (mut low, mut high) = (0, chars.len());
+        while low < high {
+            let mid = (low + high).div_ceil(2);
+            if fits(&truncated(mid)) { low = mid } else { high = mid - 1 }
+        }
+        truncated(low).into_iter().collect()
+    }
+}
+
+impl TextBox<'_, '_> {
+    /// The width a line of text will take up after tracking and horizontal scaling.
+    fn truncation_width(&self, chars: &[char]) -> f32 {
+        let scale_x = match self.horizontal_scale {
+            HorizontalScale::Factor(factor) => factor,
+            HorizontalScale::Fill => 1.0,
+        };
+        let width = chars.iter().map(|&c| self.font.metrics(c, self.size).advance_width.ceil()).sum::<f32>();
+        width * scale_x + self.tracking * chars.len().saturating_sub(1) as f32
+    }
+
+    pub(crate) fn truncate<'a>(&self, text: Cow<'a, str>) -> Cow<'a, str> {
+        let Some(truncation) = self.truncation else { return text };
+        let max_width = self.inner_bounds.width();
+        let fits = |chars: &[char]| self.truncation_width(chars) <= max_width;
+        let overflows = |line: &str| !fits(&line.chars().collect::<Vec<_>>());
+        if !text.split('\n').any(overflows) { return text }
+        Cow::Owned(text.split('\n').map(|line| if overflows(line) {
+            truncation.truncate_line(line, fits)
+        } else {
+            line.to_owned()
+        }).collect::<Vec<_>>().join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Truncates to at most `max_chars` chars, treating every char as equally wide.
+    fn truncate(truncation: Truncation, line: &str, max_chars: usize) -> String {
+        truncation.truncate_line(line, |chars| chars.len() <= max_chars)
+    }
+
+    #[test]
+    fn truncate_end() {
+        assert_eq!(truncate(Truncation::End, "verylongfilename", 10), "verylongf…");
+        assert_eq!(truncate(Truncation::End, "verylongfilename", 1), "…");
+    }
+
+    #[test]
+    fn truncate_middle() {
+        assert_eq!(truncate(Truncation::Middle, "verylongfilename.txt", 10), "veryl….txt");
+        assert_eq!(truncate(Truncation::Middle, "verylongfilename.txt", 11), "veryl…e.txt");
+    }
+
+    #[test]
+    fn truncate_to_ellipsis_if_nothing_fits() {
+        assert_eq!(truncate(Truncation::End, "abc", 0), "…");
+        assert_eq!(truncate(Truncation::Middle, "abc", 0), "…");
+    }
+}
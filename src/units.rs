@@ -0,0 +1,121 @@
+use {
+    alloc::borrow::ToOwned as _,
+    core::str::FromStr,
+    crate::Error,
+};
+
+/// A font size or other length, in pixels, a physical unit, or relative to a font size.
+/// Physical units are converted to pixels using the output resolution set with [`Builder::dpi`](crate::Builder::dpi).
+///
+/// Parses from and deserializes from strings like `24px`, `12pt`, `4.5mm`, or `1.5em`. Plain numbers deserialize as pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Size {
+    Px(f32),
+    /// Typographic points of 1/72 inch.
+    Pt(f32),
+    Mm(f32),
+    /// Relative to the font size of the text box, e.g. for tracking that scales with the text. A font size in em is relative to the root size instead.
+    Em(f32),
+    /// Relative to the root size set with [`Builder::root_size`](crate::Builder::root_size), so a theme can be scaled by changing a single size.
+    Rem(f32),
+}
+
+impl Size {
+    /// The size in pixels at the given resolution in dots per inch, font size, and root size.
+    pub fn to_px(self, dpi: f32, em: f32, rem: f32) -> f32 {
+        match self {
+            Self::Px(px) => px,
+            Self::Pt(pt) => pt * dpi / 72.0,
+            Self::Mm(mm) => mm * dpi / 25.4,
+            Self::Em(factor) => factor * em,
+            Self::Rem(factor) => factor * rem,
+        }
+    }
+}
+
+impl From<f32> for Size {
+    fn from(px: f32) -> Self {
+        Self::Px(px)
+    }
+}
+
+impl FromStr for Size {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let parse = |value: &str| value.parse().map_err(|_| Error::StyleToken(s.to_owned()));
+        if let Some(px) = s.strip_suffix("px") {
+            Ok(Self::Px(parse(px)?))
+        } else if let Some(pt) = s.strip_suffix("pt") {
+            Ok(Self::Pt(parse(pt)?))
+        } else if let Some(mm) = s.strip_suffix("mm") {
+            Ok(Self::Mm(parse(mm)?))
+        } else if let Some(rem) = s.strip_suffix("rem") {
+            Ok(Self::Rem(parse(rem)?))
+        } else if let Some(em) = s.strip_suffix("em") {
+            Ok(Self::Em(parse(em)?))
+        } else {
+            Err(Error::StyleToken(s.to_owned()))
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Size {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use alloc::string::String;
+
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Px(f32),
+            Str(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Px(px) => Ok(Self::Px(px)),
+            Repr::Str(s) => s.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_absolute_sizes() {
+        assert_eq!("24px".parse::<Size>().unwrap(), Size::Px(24.0));
+        assert_eq!("12pt".parse::<Size>().unwrap(), Size::Pt(12.0));
+        assert_eq!("4.5mm".parse::<Size>().unwrap(), Size::Mm(4.5));
+        assert_eq!("-1px".parse::<Size>().unwrap(), Size::Px(-1.0));
+    }
+
+    #[test]
+    fn reject_invalid_sizes() {
+        for size in ["", "24", "px", "24 px", "24PX", "24in", "twelvept"] {
+            assert!(matches!(size.parse::<Size>(), Err(Error::StyleToken(token)) if token == size), "{size:?}");
+        }
+    }
+
+    #[test]
+    fn convert_physical_units() {
+        assert_eq!(Size::Pt(12.0).to_px(96.0, 0.0, 0.0), 16.0);
+        assert_eq!(Size::Mm(25.4).to_px(300.0, 0.0, 0.0), 300.0);
+        assert_eq!(Size::Px(7.0).to_px(300.0, 0.0, 0.0), 7.0);
+    }
+
+    #[test]
+    fn parse_relative_sizes() {
+        assert_eq!("1.5em".parse::<Size>().unwrap(), Size::Em(1.5));
+        // `rem` also ends in `em`, so it has to be recognized first
+        assert_eq!("2rem".parse::<Size>().unwrap(), Size::Rem(2.0));
+        assert!("rem".parse::<Size>().is_err());
+    }
+
+    #[test]
+    fn convert_relative_sizes() {
+        assert_eq!(Size::Em(1.5).to_px(96.0, 20.0, 16.0), 30.0);
+        assert_eq!(Size::Rem(2.0).to_px(96.0, 20.0, 16.0), 32.0);
+    }
+}
@@ -0,0 +1,49 @@
+use {
+    tiny_skia::{
+        FilterQuality,
+        IntRect,
+        Pixmap,
+        PixmapMut,
+        PixmapPaint,
+        Transform,
+    },
+    crate::{
+        Error,
+        GlyphCache,
+        TextBox,
+    },
+};
+#[cfg(not(any(feature = "std", test)))] use crate::float::Float as _;
+
+impl TextBox<'_, '_> {
+    /// Repeats the text across the whole canvas, rotated by `angle` degrees around the canvas center, with alternate rows staggered by half a tile.
+    ///
+    /// `spacing` is the horizontal and vertical gap between copies. The text box's own position on the canvas is ignored.
+    pub fn draw_watermark(&self, mut canvas: PixmapMut<'_>, glyph_cache: &mut GlyphCache, angle: f32, [spacing_x, spacing_y]: [f32; 2], opacity: f32) -> Result<(), Error> {
+        let (width, height) = (canvas.width(), canvas.height());
+        let mut scratch = Pixmap::new(width, height).ok_or(Error::Canvas { width, height })?;
+        let Some(dirty) = self.draw(scratch.as_mut(), glyph_cache)? else { return Ok(()) };
+        let Some(tile) = dirty.round_out().and_then(|rect| IntRect::from_xywh(rect.x(), rect.y(), rect.width(), rect.height())).and_then(|rect| scratch.clone_rect(rect)) else { return Ok(()) };
+        let cell_width = tile.width() as f32 + spacing_x.max(0.0);
+        let cell_height = tile.height() as f32 + spacing_y.max(0.0);
+        let (center_x, center_y) = (width as f32 / 2.0, height as f32 / 2.0);
+        let reach = center_x.hypot(center_y);
+        let columns = (reach / cell_width).ceil() as i32 + 1;
+        let rows = (reach / cell_height).ceil() as i32 + 1;
+        let paint = PixmapPaint {
+            opacity: opacity.clamp(0.0, 1.0),
+            quality: FilterQuality::Bilinear,
+            ..PixmapPaint::default()
+        };
+        let rotation = Transform::from_rotate_at(angle, center_x, center_y);
+        for row in -rows..=rows {
+            let stagger = if row % 2 == 0 { 0.0 } else { cell_width / 2.0 };
+            for column in -columns..=columns {
+                let x = center_x + column as f32 * cell_width + stagger - tile.width() as f32 / 2.0;
+                let y = center_y + row as f32 * cell_height - tile.height() as f32 / 2.0;
+                canvas.draw_pixmap(0, 0, tile.as_ref(), &paint, rotation.pre_translate(x, y), None);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,199 @@
+use {
+    hashbrown::HashMap,
+    crate::{
+        Error,
+        GlyphCache,
+        GlyphKey,
+        TextBox,
+    },
+};
+
+const PADDING: u32 = 1;
+
+/// A vertex of a textured glyph quad. `position` is in canvas pixels, `uv` is normalized to the atlas texture.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(C)]
+pub struct Vertex {
+    pub position: [f32; 2],
+    pub uv: [f32; 2],
+    pub opacity: f32,
+}
+
+impl Vertex {
+    pub const ATTRIBUTES: [wgpu::VertexAttribute; 3] = wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Float32];
+
+    pub fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// Premultiplied RGBA8 pixels that need to be written to the atlas texture before drawing.
+#[derive(Debug, Clone)]
+pub struct AtlasUpdate {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl AtlasUpdate {
+    pub fn write(&self, queue: &wgpu::Queue, texture: &wgpu::Texture) {
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x: self.x, y: self.y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            &self.data,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * self.width),
+                rows_per_image: Some(self.height),
+            },
+            wgpu::Extent3d { width: self.width, height: self.height, depth_or_array_layers: 1 },
+        );
+    }
+}
+
+/// Tracks which glyphs have been uploaded to a GPU atlas texture, packed into shelves.
+#[derive(Debug)]
+pub struct GpuAtlas {
+    width: u32,
+    height: u32,
+    entries: HashMap<GlyphKey, [u32; 4]>,
+    cursor: (u32, u32),
+    shelf_height: u32,
+    pending: Vec<AtlasUpdate>,
+}
+
+impl GpuAtlas {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            entries: HashMap::default(),
+            cursor: (PADDING, PADDING),
+            shelf_height: 0,
+            pending: Vec::default(),
+            width, height,
+        }
+    }
+
+    pub fn width(&self) -> u32 { self.width }
+    pub fn height(&self) -> u32 { self.height }
+
+    /// A descriptor for a texture matching this atlas. The texture is expected to hold premultiplied alpha.
+    pub fn texture_descriptor(&self) -> wgpu::TextureDescriptor<'static> {
+        wgpu::TextureDescriptor {
+            label: Some("text glyph atlas"),
+            size: wgpu::Extent3d { width: self.width, height: self.height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        }
+    }
+
+    /// Forgets all uploaded glyphs, e.g. after the atlas has filled up. The texture contents can be left as is.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.cursor = (PADDING, PADDING);
+        self.shelf_height = 0;
+        self.pending.clear();
+    }
+
+    pub fn take_updates(&mut self) -> Vec<AtlasUpdate> {
+        core::mem::take(&mut self.pending)
+    }
+
+    pub fn write_updates(&mut self, queue: &wgpu::Queue, texture: &wgpu::Texture) {
+        for update in self.take_updates() {
+            update.write(queue, texture);
+        }
+    }
+
+    fn allocate(&mut self, width: u32, height: u32) -> Result<(u32, u32), Error> {
+        if self.cursor.0 + width + PADDING > self.width {
+            self.cursor = (PADDING, self.cursor.1 + self.shelf_height + PADDING);
+            self.shelf_height = 0;
+        }
+        if self.cursor.0 + width + PADDING > self.width || self.cursor.1 + height + PADDING > self.height {
+            return Err(Error::AtlasFull { width: self.width, height: self.height })
+        }
+        let position = self.cursor;
+        self.cursor.0 += width + PADDING;
+        self.shelf_height = self.shelf_height.max(height);
+        Ok(position)
+    }
+}
+
+/// Buffers for drawing a text box as indexed triangles sampling from a `GpuAtlas` texture.
+#[derive(Debug, Clone, Default)]
+pub struct GpuText {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+    pub atlas_updates: Vec<AtlasUpdate>,
+}
+
+impl GpuText {
+    pub fn vertex_bytes(&self) -> Vec<u8> {
+        self.vertices.iter()
+            .flat_map(|vertex| vertex.position.into_iter().chain(vertex.uv).chain([vertex.opacity]))
+            .flat_map(f32::to_ne_bytes)
+            .collect()
+    }
+
+    pub fn index_bytes(&self) -> Vec<u8> {
+        self.indices.iter().copied().flat_map(u32::to_ne_bytes).collect()
+    }
+}
+
+impl TextBox<'_, '_> {
+    pub fn gpu_buffers(&self, atlas: &mut GpuAtlas, glyph_cache: &mut GlyphCache) -> Result<GpuText, Error> {
+        if let Some((c, byte_offset)) = self.control_char {
+            return Err(Error::ControlChar { c, byte_offset })
+        }
+        #[cfg(feature = "rayon")] self.prerasterize(glyph_cache)?;
+        let opacity = self.opacity.clamp(0.0, 1.0);
+        let mut text = GpuText::default();
+        for (idx, glyph) in self.glyphs.iter().enumerate() {
+            if glyph.width == 0 || glyph.height == 0 { continue }
+            for (key, padding) in self.glyph_layers(idx) {
+                let [x, y, width, height] = if let Some(&entry) = atlas.entries.get(&key) {
+                    entry
+                } else {
+                    let pixmap = self.cached_glyph(glyph, key, glyph_cache)?;
+                    let (x, y) = atlas.allocate(pixmap.width(), pixmap.height())?;
+                    atlas.pending.push(AtlasUpdate { width: pixmap.width(), height: pixmap.height(), data: pixmap.data().to_vec(), x, y });
+                    let entry = [x, y, pixmap.width(), pixmap.height()];
+                    atlas.entries.insert(key, entry);
+                    entry
+                };
+                let [u0, v0, u1, v1] = [
+                    x as f32 / atlas.width as f32,
+                    y as f32 / atlas.height as f32,
+                    (x + width) as f32 / atlas.width as f32,
+                    (y + height) as f32 / atlas.height as f32,
+                ];
+                let [x0, y0] = [glyph.x - padding, glyph.y - padding];
+                let [x1, y1] = [x0 + width as f32, y0 + height as f32];
+                let base = text.vertices.len() as u32;
+                text.vertices.extend([
+                    Vertex { position: [x0, y0], uv: [u0, v0], opacity },
+                    Vertex { position: [x1, y0], uv: [u1, v0], opacity },
+                    Vertex { position: [x1, y1], uv: [u1, v1], opacity },
+                    Vertex { position: [x0, y1], uv: [u0, v1], opacity },
+                ]);
+                text.indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+            }
+        }
+        text.atlas_updates = atlas.take_updates();
+        Ok(text)
+    }
+}
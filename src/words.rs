@@ -0,0 +1,71 @@
+use {
+    alloc::vec::Vec,
+    core::ops::Range,
+    itertools::Itertools as _,
+    tiny_skia::Rect,
+    crate::TextBox,
+};
+
+/// A piece of the laid out text along with its position in the text and on the canvas.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextSpan<'a> {
+    pub text: &'a str,
+    /// The byte range of `text` in the text appended to the text box, after preprocessing such as newline normalization.
+    pub range: Range<usize>,
+    pub rect: Rect,
+}
+
+impl TextBox<'_, '_> {
+    /// The byte range and bounding box of each word, i.e. each run of non-whitespace glyphs on a line, in text order.
+    ///
+    /// A word broken across lines yields one box per line.
+    pub fn word_rects(&self) -> Vec<(Range<usize>, Rect)> {
+        let offsets = self.glyph_byte_offsets();
+        self.lines.iter()
+            .flat_map(|line| (line.glyph_start..=line.glyph_end)
+                .filter(|&idx| idx < self.glyphs.len())
+                .chunk_by(|&idx| !self.glyphs[idx].parent.is_whitespace())
+                .into_iter()
+                .filter(|(is_word, _)| *is_word)
+                .filter_map(|(_, word)| {
+                    let word = word.collect::<Vec<_>>();
+                    let (&first, &last) = (word.first()?, word.last()?);
+                    let (left, right) = word.iter()
+                        .map(|&idx| self.glyph_extent(&self.glyphs[idx]))
+                        .reduce(|(left1, right1), (left2, right2)| (left1.min(left2), right1.max(right2)))?;
+                    Some((offsets[first]..offsets[last] + self.glyphs[last].parent.len_utf8(), self.span_rect(line, left, right)?))
+                })
+                .collect::<Vec<_>>()
+            )
+            .collect()
+    }
+
+    /// The words of the text, in text order. See `word_rects`.
+    pub fn words(&self) -> impl Iterator<Item = TextSpan<'_>> {
+        self.word_rects().into_iter().map(|(range, rect)| TextSpan { text: self.text.get(range.clone()).unwrap_or_default(), range, rect })
+    }
+
+    /// The laid out lines of the text, excluding line breaks. The rect of a line covers its visible glyphs.
+    pub fn lines(&self) -> impl Iterator<Item = TextSpan<'_>> {
+        let offsets = self.glyph_byte_offsets();
+        self.lines.iter().filter_map(move |line| {
+            let glyphs = self.glyphs.get(line.glyph_start..=line.glyph_end)?;
+            let start = offsets[line.glyph_start];
+            let end = glyphs.iter().zip(&offsets[line.glyph_start..]).rfind(|(glyph, _)| glyph.parent != '\n')
+                .map_or(start, |(glyph, offset)| offset + glyph.parent.len_utf8());
+            let (left, right) = glyphs.iter()
+                .filter(|glyph| !glyph.parent.is_whitespace())
+                .map(|glyph| self.glyph_extent(glyph))
+                .reduce(|(left1, right1), (left2, right2)| (left1.min(left2), right1.max(right2)))
+                .unwrap_or_else(|| {
+                    let (origin, _) = self.glyph_extent(&glyphs[0]);
+                    (origin, origin)
+                });
+            Some(TextSpan {
+                text: self.text.get(start..end).unwrap_or_default(),
+                range: start..end,
+                rect: self.span_rect(line, left, right)?,
+            })
+        })
+    }
+}